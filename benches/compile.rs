@@ -0,0 +1,57 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use rusty_calculator::compile::{compile, Env};
+use rusty_calculator::evaluate::evaluate;
+use rusty_calculator::infix_to_postfix::infix_to_postfix;
+use rusty_calculator::tokenize::tokenize;
+use std::collections::HashMap;
+use std::f64::consts;
+use std::hint::black_box;
+
+const EQUATIONS: [(&str, &str); 3] = [
+    ("polynomial", "3x^2 + 2x*y - y/4 + 7"),
+    ("roots", "R(x^2 + y^2) + 3R(x*y) - NA(x - y)"),
+    ("conditions", "(x < y)*x + (x >= y)*y + (x != 0 and y != 0)"),
+];
+
+// Values of x and y for each evaluation, as a batch workload would change them.
+fn inputs() -> Vec<(f64, f64)> {
+    (1..=100).map(|i| (i as f64 * 0.37, 100.0 - i as f64 * 0.61)).collect()
+}
+
+// Solves each equation for every input through tokenize, infix_to_postfix, and evaluate,
+// and through a compiled expression.
+fn compiled_against_string_path(c: &mut Criterion) {
+    let inputs = inputs();
+    for (name, equation) in EQUATIONS {
+        let mut group = c.benchmark_group(name);
+        group.bench_function("string path", |b| {
+            let mut variables: HashMap<char, String> = HashMap::new();
+            variables.insert('p', consts::PI.to_string());
+            variables.insert('e', consts::E.to_string());
+            b.iter(|| {
+                for (x, y) in &inputs {
+                    variables.insert('x', x.to_string());
+                    variables.insert('y', y.to_string());
+                    let tokens = tokenize(black_box(equation), &variables).unwrap();
+                    black_box(evaluate(infix_to_postfix(&tokens)).unwrap());
+                }
+            });
+        });
+        group.bench_function("compiled", |b| {
+            let compiled = compile(equation).unwrap();
+            let mut env = Env::new();
+            b.iter(|| {
+                for (x, y) in &inputs {
+                    env.set('x', *x).unwrap();
+                    env.set('y', *y).unwrap();
+                    black_box(compiled.eval(black_box(&env)).unwrap());
+                }
+            });
+        });
+        group.bench_function("compile", |b| b.iter(|| compile(black_box(equation)).unwrap()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, compiled_against_string_path);
+criterion_main!(benches);
@@ -1,14 +1,32 @@
 use crate::test;
-use crate::tokenize::tokenize;
+use crate::tokenize::tokenize_with_context;
 use crate::infix_to_postfix::infix_to_postfix;
 use crate::evaluate::evaluate;
+use crate::config;
+use crate::constants::{self, Constant, CONSTANTS};
+use crate::format::NumberFormat;
+use crate::plot;
+use crate::function::Function;
+use crate::output::{self, ErrorKind};
+use crate::script;
+use crate::session::{self, Session};
+use crate::settings::{self, Settings};
+use crate::undo;
+use crate::value::Value;
 use std::collections::HashMap;
+use std::f64::consts;
+use std::path::Path;
+
+// Commands accepted by execute_command, used for tab completion.
+pub const COMMANDS: [&str; 24] = ["/help", "/reset", "/variables", "/constants", "/functions", "/history", "/undo",
+                                  "/redo", "/x", "/test", "/reveal", "/set", "/get", "/format", "/save", "/load",
+                                  "/run", "/config", "/plot", "/info", "/op", "/varop", "/complexop", "/oporder"];
 
 // Initializes and returns a default variable map.
 pub fn get_variable_map() -> HashMap<char, String> {
     let variables: HashMap<char, String> = [
-        ('p', "3.14159265359".to_string()),
-        ('e', "2.71828182845".to_string()),
+        ('p', consts::PI.to_string()),
+        ('e', consts::E.to_string()),
         ('=', "0".to_string()),
         ('i', "1".to_string()),
         ('j', "1".to_string()),
@@ -21,108 +39,322 @@ pub fn get_variable_map() -> HashMap<char, String> {
     variables
 }
 
-// Initializes and returns a default settings map.
-pub fn get_settings_map() -> HashMap<String, bool> {
-    let settings: HashMap<String, bool> = [
-        ("reveal".to_string(), false),
-        ("quit".to_string(), false),
-    ].iter().cloned().collect();
-    settings
-}
-
-// Calls correct command function.
-pub fn execute_command(command: &str, 
-                        variables: &mut HashMap<char, String>, 
-                        settings: &mut HashMap<String, bool>) {
-    match command {
-        "/help" => help(),
-        "/reset" => reset(variables, settings),
-        "/variables" => print_variables(variables),
-        "/x" => set_quit(settings),
-        "/test" => test::run_tests(),
-        "/reveal" => set_reveal(settings),
-        "/info" => print_info(),
-        "/op" => print_operators(),
-        "/varop" => print_variable_info(),
-        "/complexop" => print_complex_operators(),
-        "/oporder" => print_operation_order(),
-        _ => println!("{} is not a valid command. Try /help.", command),
+// Calls correct command function and prints its output or error. Returns false if the command failed.
+pub fn execute_command(command: &str, session: &mut Session) -> bool {
+    let result = run_command(command, session);
+    print_command(command, result, (0, command.len()), session)
+}
+
+// Calls correct command function and returns its output.
+pub fn run_command(command: &str, session: &mut Session) -> Result<String, String> {
+    let (name, argument) = match command.split_once(' ') {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (command, ""),
+    };
+
+    match name {
+        "/help" => Ok(help()),
+        "/reset" => reset(session),
+        "/variables" => Ok(print_variables(&session.variables, &session.settings.format)),
+        "/constants" => print_constants(argument, &session.settings.format),
+        "/functions" => Ok(print_functions(&session.functions)),
+        "/history" => history(argument, session),
+        "/undo" => undo::undo(session),
+        "/redo" => undo::redo(session),
+        "/x" => {
+            session.quit = true;
+            Ok(String::new())
+        },
+        "/test" => Ok(test::run_tests()),
+        "/reveal" => Ok(set_reveal(&mut session.settings)),
+        "/set" => set_setting(argument, &mut session.settings),
+        "/get" => print_settings(argument, &session.settings),
+        "/format" => set_format(argument, session),
+        "/save" => save_session(argument, session),
+        "/load" => load_session(argument, session),
+        "/run" => run_script(argument, session),
+        "/config" => reload_config(argument, session),
+        "/plot" => plot_equation(argument, session),
+        "/info" => Ok(info()),
+        "/op" => Ok(operator_info()),
+        "/varop" => Ok(variable_info()),
+        "/complexop" => Ok(complex_operator_info()),
+        "/oporder" => Ok(operation_order()),
+        // Aliases always name built in commands, so they are never expanded twice.
+        _ if session.config.aliases.contains_key(name) => {
+            let command = format!("{} {}", session.config.aliases[name], argument);
+            run_command(command.trim(), session)
+        },
+        _ => Err(format!("{} is not a valid command. Try /help.", command)),
+    }
+}
+
+// Prints a command's output or error. span is the byte range of the command within the input line.
+// Returns false if the command failed.
+pub(crate) fn print_command(command: &str, result: Result<String, String>, span: (usize, usize),
+                            session: &Session) -> bool {
+    // Output follows the output setting after the command, so '/format json' is answered in JSON.
+    let json = session.settings.json();
+    match result {
+        Ok(text) => {
+            if !text.is_empty() {
+                output::print_output(&text, json);
+            }
+            true
+        },
+        Err(e) => {
+            output::print_error(command, ErrorKind::Command, &e, Some(span), json);
+            false
+        },
     }
 }
 
 // Changes the value of a user variable to a user-specified value or operation result.
-pub fn change_variable(command: &str, variables: &mut HashMap<char, String>) {
-    if let Some(variable) = command.chars().nth(1) {
-        match variable {
-            'p' | 'e' | '=' => println!("{} is not a mutable variable", variable),
-            'i'..='o' => {
-                let equation = &command[2..];
-                match tokenize(equation, variables) {
-                    Ok(result) => {
-                        match evaluate(infix_to_postfix(&result)) {
-                            Ok(result) => { 
-                                variables.insert(variable, result.to_string());
-                                println!("'{}' value changed to {}", variable, result);
-                            },
-                            Err(e) => { println!("Unable to evaluate new value: {}", e); },
-                        }
-                    },
-                    Err(e) => println!("Unable to tokenize new value: {}", e),
-                }
-            },
-            _ => println!("{} is not a valid variable", variable)
-        }
+// Returns the changed variable and its new value.
+pub fn change_variable(command: &str, session: &mut Session) -> Result<(char, Value), String> {
+    let variable = command.chars().nth(1).ok_or_else(|| "Missing variable name".to_string())?;
+    check_mutable(variable)?;
+    let equation = command.char_indices().nth(2).map_or("", |(index, _)| &command[index..]);
+    let tokens = tokenize_with_context(equation, session.context())
+        .map_err(|e| format!("Unable to tokenize new value: {}", e))?;
+    let result = evaluate(infix_to_postfix(&tokens))
+        .map_err(|e| format!("Unable to evaluate new value: {}", e))?;
+    session.variables.insert(variable, result.to_string());
+    Ok((variable, result))
+}
+
+// Checks that a variable can be changed by the user.
+pub fn check_mutable(variable: char) -> Result<(), String> {
+    match variable {
+        'p' | 'e' | '=' => Err(format!("{} is not a mutable variable", variable)),
+        'i'..='o' => Ok(()),
+        variable => Err(format!("{} is not a valid variable", variable)),
     }
 }
 
-// Prints helpful command info.
-fn help() {
-    println!("Help:\n\
-    /x         -> Quit calculator\n\
-    /reset     -> Reset variable and setting values to default\n\
+// Helpful command info.
+fn help() -> String {
+    "Help:\n\
+    /x         -> Quit calculator and save the session for next launch\n\
+    /reset     -> Reset variable and setting values to default and remove functions and results\n\
     /variables -> Print all current variable values\n\
+    /constants -> Print library constants, used as '@tau' or '@phys.c'. '/constants phys'
+              prints one namespace and '/constants c' one constant\n\
+    /undo      -> Revert the last change to variables, settings, or functions, like
+              '_i(4)', /reset, /format, or /load\n\
+    /redo      -> Make the last undone change again\n\
+    /history   -> Print numbered results. '$3' uses result 3 and '$$' the last one.
+              '/history clear' removes all results\n\
     /test      -> Run all test cases\n\
     /reveal    -> Toggle reveal on and off. When reveal is on, separate tokens and postfix
               expressions will be printed alongside the result for each user entered equation\n\
+    /set s v   -> Change setting s to v: reveal on|off, output text|json, notation
+              auto|fixed|sig|sci|eng, precision 0-100, grouping on|off, rounding mode,
+              locale en|de|fr, uncertainty gaussian|interval, or seed n|random\n\
+    /get s     -> Print the value of setting s, or all settings without s\n\
+    /format f  -> Change output format to 'text' or 'json'. JSON prints one object per
+              equation with its input and result or error\n\
+    /format n  -> Change how numbers are shown. n is 'auto', 'fixed digits', 'sig digits',
+              'sci digits', or 'eng digits'. Fixed digits are decimals. The others
+              are significant digits. 'grouping on' or 'off' toggles thousands
+              separators, and 'rounding mode' sets half-up, half-even, half-down,
+              up, down, floor, or ceiling rounding. 'locale en', 'de', or 'fr' sets
+              the decimal and group separators. /format prints the current format\n\
+    /save file -> Save variables, functions, settings, and history to file\n\
+    /load file -> Restore variables, functions, settings, and history from file\n\
+    /run file  -> Run a script file. See /info for script statements\n\
+    /config    -> Print the path of the config file. '/config reload' reads it again\n\
+    /plot f    -> Plot equation f of x between two values, as in '/plot x^2 - 1, -2, 2'\n\
+    /functions -> Print all user defined functions\n\
     /info      -> Prints behavior and general usage info\n\
     /op        -> Prints basic operator usage information\n\
     /varop     -> Prints variable usage information\n\
     /complexop -> Prints complex operator usage information\n\
-    /oporder   -> Prints order of operations");
+    /oporder   -> Prints order of operations".to_string()
 }
 
-// Resets variables and settings maps to their default values.
-fn reset(variables: &mut HashMap<char, String>, settings: &mut HashMap<String, bool>) {
-    *variables = get_variable_map();
-    *settings = get_settings_map();
-    println!("Variables and settings reset successfully");
+// Resets variables and settings to their default values and removes user functions and results.
+// The config is applied again, since it holds the defaults chosen by the user.
+fn reset(session: &mut Session) -> Result<String, String> {
+    session.variables = get_variable_map();
+    session.settings = Settings::default();
+    session.functions.clear();
+    session.results.clear();
+    config::apply(std::mem::take(&mut session.config), session)?;
+    Ok("Variables, functions, settings, and results reset successfully".to_string())
 }
 
-// Prints current variable values.
-fn print_variables(variables: &mut HashMap<char, String>) {
-    println!("Variables:");
+// Lists current variable values.
+fn print_variables(variables: &HashMap<char, String>, format: &NumberFormat) -> String {
+    let mut lines = vec!["Variables:".to_string()];
     for (key, value) in variables {
-        println!("| {}: {}", key, value);
+        lines.push(format!("| {}: {}", key, format.format_text(value)));
     }
+    lines.join("\n")
 }
 
-// Notifies the main loop that it should return.
-fn set_quit(settings: &mut HashMap<String, bool>) {
-    settings.insert("quit".to_string(), true);
+// Lists library constants with their units, filtered to a namespace or a single constant.
+fn print_constants(filter: &str, format: &NumberFormat) -> Result<String, String> {
+    let listed: Vec<&Constant> = match filter {
+        "" => CONSTANTS.iter().collect(),
+        _ if CONSTANTS.iter().any(|constant| constant.namespace == filter) => {
+            CONSTANTS.iter().filter(|constant| constant.namespace == filter).collect()
+        },
+        _ => vec![constants::find(filter)?],
+    };
+    let mut lines = vec!["Constants:".to_string()];
+    for constant in listed {
+        let value = format.format(&Value::Float(constant.value));
+        let unit = if constant.unit.is_empty() { String::new() } else { format!(" {}", constant.unit) };
+        lines.push(format!("| @{}.{}: {}{}, {}", constant.namespace, constant.name, value, unit,
+                           constant.description));
+    }
+    Ok(lines.join("\n"))
+}
+
+// Lists user defined functions.
+fn print_functions(functions: &HashMap<String, Function>) -> String {
+    let mut names: Vec<&String> = functions.keys().collect();
+    names.sort();
+    let mut lines = vec!["Functions:".to_string()];
+    for name in names {
+        lines.push(format!("| {}", functions[name].definition(name)));
+    }
+    lines.join("\n")
+}
+
+// Lists numbered results, or removes them with 'clear'.
+fn history(argument: &str, session: &mut Session) -> Result<String, String> {
+    match argument {
+        "" => {
+            let mut lines = vec!["History:".to_string()];
+            for (number, calculation) in session.results.iter().enumerate() {
+                lines.push(format!("| ${}: {} = {}", number + 1, calculation.input,
+                                   session.settings.format.format_text(&calculation.result)));
+            }
+            Ok(lines.join("\n"))
+        },
+        "clear" => {
+            session.results.clear();
+            Ok("History cleared".to_string())
+        },
+        _ => Err("Expected /history or /history clear".to_string()),
+    }
 }
 
 // Toggles reveal settings value.
-fn set_reveal(settings: &mut HashMap<String, bool>) {
-    settings.insert("reveal".to_string(), !settings["reveal"]); 
-    println!("Reveal changed to {}", settings["reveal"]);
+fn set_reveal(settings: &mut Settings) -> String {
+    settings.reveal = !settings.reveal;
+    format!("Reveal changed to {}", settings.reveal)
+}
+
+// Changes a setting given as 'name value'.
+fn set_setting(argument: &str, settings: &mut Settings) -> Result<String, String> {
+    let (name, value) = match argument.split_once(' ') {
+        Some((name, value)) => (name, value.trim()),
+        None => (argument, ""),
+    };
+    match (name, value) {
+        ("", _) => Err("Missing setting name and value. Try /set precision 4".to_string()),
+        (_, "") => settings.get(name).and_then(|_| Err(format!("Missing value. Try /set {} value", name))),
+        _ => settings.set(name, value),
+    }?;
+    Ok(format!("{} changed to {}", name, value))
+}
+
+// Lists one setting, or all of them when no name is given.
+fn print_settings(name: &str, settings: &Settings) -> Result<String, String> {
+    if name.is_empty() {
+        let mut lines = vec!["Settings:".to_string()];
+        for name in settings::NAMES {
+            if let Ok(value) = settings.get(name) {
+                lines.push(format!("| {}: {}", name, value));
+            }
+        }
+        return Ok(lines.join("\n"));
+    }
+    Ok(format!("{}: {}", name, settings.get(name)?))
+}
+
+// Changes how results are written: text or JSON output, number notation, grouping, and rounding.
+// Returns the current format when no argument is given.
+fn set_format(argument: &str, session: &mut Session) -> Result<String, String> {
+    let (name, value) = match argument.split_once(' ') {
+        Some((name, value)) => (name, value.trim()),
+        None => (argument, ""),
+    };
+    let settings = &mut session.settings;
+
+    match (name, value) {
+        ("", _) => Ok(()),
+        ("json" | "text", "") => settings.set("output", name),
+        ("grouping" | "rounding" | "locale", _) => settings.set(name, value),
+        _ => settings.format.set_notation(argument).map_err(|e| format!("{}. Try /help", e)),
+    }?;
+    Ok(format!("Format: {}, output {}", settings.format, settings.get("output").unwrap_or_default()))
+}
+
+// Saves the session to a user specified file.
+fn save_session(file: &str, session: &Session) -> Result<String, String> {
+    if file.is_empty() {
+        return Err("Missing file name. Try /save file".to_string());
+    }
+    session::save(session, Path::new(file))?;
+    Ok(format!("Session saved to {}", file))
+}
+
+// Replaces the session with one loaded from a user specified file.
+fn load_session(file: &str, session: &mut Session) -> Result<String, String> {
+    if file.is_empty() {
+        return Err("Missing file name. Try /load file".to_string());
+    }
+    let loaded = session::load(Path::new(file))?;
+    // Keep undo history so the load itself can be undone, and the config for aliases and /reset.
+    let (changes, config) = (std::mem::take(&mut session.changes), std::mem::take(&mut session.config));
+    *session = loaded;
+    session.changes = changes;
+    session.config = config;
+    Ok(format!("Session loaded from {}", file))
+}
+
+// Reads the config file again and applies it, or returns where it is read from.
+fn reload_config(argument: &str, session: &mut Session) -> Result<String, String> {
+    let path = config::default_path()
+        .ok_or_else(|| "Unable to find the config directory. Set XDG_CONFIG_HOME or HOME".to_string())?;
+    match argument {
+        "" => Ok(format!("Config file: {}", path.display())),
+        "reload" => {
+            config::load(&path).and_then(|loaded| config::apply(loaded, session))?;
+            Ok(format!("Config reloaded from {}", path.display()))
+        },
+        _ => Err("Expected /config or /config reload".to_string()),
+    }
+}
+
+// Runs a script file with the current session. The script prints its own results.
+fn run_script(file: &str, session: &mut Session) -> Result<String, String> {
+    if file.is_empty() {
+        return Err("Missing file name. Try /run file".to_string());
+    }
+    script::run_file(Path::new(file), session)?;
+    Ok(String::new())
+}
+
+// Plots an equation of x between two values as a chart in the terminal.
+fn plot_equation(argument: &str, session: &Session) -> Result<String, String> {
+    if argument.is_empty() {
+        return Err("Missing equation. Try /plot x^2 - 1, -2, 2".to_string());
+    }
+    plot::plot(argument, session.context())
 }
 
-// Prints basic Rusty Calculator behavior information.
-fn print_info() {
-    println!("General Information:\n\n\
+// Basic Rusty Calculator behavior information.
+fn info() -> String {
+    "General Information:\n\n\
     Numbers:\n\
     | Numbers can be entered in integer and floating point format.\n\
-    | Use 'E' for scientific notation.\n\n\
+    | Use 'E' for scientific notation.\n\
+    | Integer results of +, -, *, /, //, %, ^, and ! are exact at any size.\n\n\
     Multiple Operations:\n\
     | Multiple operations can be queued at once with ';'.\n\
     | '4+4;2+2;4*5' will evaluate each operation separately.\n\
@@ -133,17 +365,79 @@ fn print_info() {
     | Mutating variables must not have whitespace between the\n\
     |    underscore and the variable name (_p) but may have\n\
     |    whitespace anywhere thereafter.\n\n\
+    Line Editing:\n\
+    | Use the arrow keys to edit the current line and move through\n\
+    |    previous lines. Search previous lines with Ctrl-R.\n\
+    | Tab completes commands, function names, and variable names.\n\n\
+    Number Format:\n\
+    | '/format fixed 4' shows 4 decimals. 'sig', 'sci', and 'eng' show\n\
+    |    significant digits in positional, scientific, and engineering notation.\n\
+    | '/format grouping on' adds thousands separators and '/format rounding'\n\
+    |    chooses how hidden digits are rounded.\n\
+    | '/format locale de' uses decimal commas (1.234,5). Complex operator\n\
+    |    values are then separated by ';' as in 'M[1,5; 2,5]'.\n\
+    | Values are always stored and used at full precision.\n\n\
+    Settings:\n\
+    | '/set precision 4' changes a setting and '/get precision' prints it.\n\
+    | Settings are reveal, output, notation, precision, grouping, rounding,\n\
+    |    locale, uncertainty, and seed. '/get' prints all of them.\n\n\
+    Config File:\n\
+    | '~/.config/rusty_calculator/config.toml' sets startup [settings],\n\
+    |    [variables], [constants], [functions], and command [aliases].\n\
+    | '/config reload' reads it again.\n\n\
+    Sessions:\n\
+    | Variables, functions, the last answer, settings, the number format,\n\
+    |    results, and input history are saved on /x and restored the next\n\
+    |    time the calculator is launched.\n\
+    | Sessions are stored in '$XDG_DATA_HOME/rusty_calculator/session'\n\
+    |    (default '~/.local/share/rusty_calculator/session').\n\
+    | '/save file' and '/load file' save and restore named sessions.\n\n\
+    Undo:\n\
+    | '/undo' reverts the last change to variables, settings, the number\n\
+    |    format, or functions, and '/redo' makes it again.\n\
+    | Results and the answer '=' are not changes.\n\n\
+    Dates and Times:\n\
+    | Dates and durations are entered in braces: '{2024-03-01}',\n\
+    |    '{2024-03-01 14:30}', '{now}', '{today}', '{90 days}', '{1h 30m}'.\n\
+    | Dates are in the local time zone unless one is named, as in\n\
+    |    '{2024-03-01T14:30[America/New_York]}'.\n\
+    | Dates subtract to durations and add or subtract durations.\n\
+    |    '({2024-12-25} - {today}) / {1 day}' counts days.\n\
+    | Durations scale by numbers. Whole days are added on the calendar.\n\n\
+    Uncertainty:\n\
+    | '9.81 ± 0.02' is a measurement with an uncertainty. Operators\n\
+    |    propagate it, as in '2(9.81 ± 0.02)^2'.\n\
+    | '/set uncertainty gaussian' (the default) treats it as a standard\n\
+    |    error and combines errors to first order. '/set uncertainty interval'\n\
+    |    treats it as bounds and gives the lowest and highest results.\n\
+    | Results are rounded to their uncertainty, as in '19.62 ± 0.04'.\n\n\
+    Plotting:\n\
+    | '/plot x^2 - 1, -2, 2' draws an equation of x from -2 to 2 with\n\
+    |    braille dots, labeled axes, and lines at x = 0 and y = 0.\n\
+    | Points where the equation cannot be evaluated are left out and\n\
+    |    counted below the chart. Jumps, as in '/plot 1/x, -1, 1', are not\n\
+    |    joined.\n\n\
+    Scripts:\n\
+    | '/run file' runs a script with one statement per line:\n\
+    |    'let i = equation' sets a mutable variable.\n\
+    |    'def name(i, j) = equation' defines a function called as 'name[3, 4]'.\n\
+    |    'if equation', 'else', and 'end' run a block when equation is not 0.\n\
+    |    'while equation' and 'end' repeat a block while equation is not 0.\n\
+    |    'for i = start, end, step' and 'end' repeat a block for each value.\n\
+    |    'print value, \"text\"' prints results and quoted text.\n\
+    | Other lines are solved like calculator input. Lines starting with\n\
+    |    '#' are comments.\n\n\
     Parenthesis Balancing:\n\
     | Operations with unbalanced parenthesis will become balanced.\n\
     |    '4(4(4(4' will be automatically interpreted as '4(4(4(4)))'.\n\
     | This also applies to values entered within complex operators.\n\
     |    'S[1,4(4(4,5(5+6]' will be automatically interpreted\n\
-    |    as 'S[1,4(4(4)),5(5+6)]'.");
+    |    as 'S[1,4(4(4)),5(5+6)]'.".to_string()
 }
 
-// Prints operator information.
-fn print_operators() {
-    println!("Operators:\n\n\
+// Operator information.
+fn operator_info() -> String {
+    "Operators:\n\n\
     Addition: '+'\n\
     | Add two numbers (x+y).\n\n\
     Subtraction: '-'\n\
@@ -178,23 +472,41 @@ fn print_operators() {
     |    side lengths x and y (xHy).\n\
     | Always returns a positive length value.\n\n\
     Absolute Value: 'A'\n\
-    | Computes absolute value of x (Ax).");
+    | Computes absolute value of x (Ax).\n\n\
+    Uncertainty: '±'\n\
+    | Enters x with an uncertainty of y (x±y), as in '9.81 ± 0.02'.\n\
+    | Uncertainties propagate through operators. See /info.\n\n\
+    Comparison: '<', '<=', '==', '!=', '>', '>='\n\
+    | Compares two numbers (x<y). Results are 1 if true and 0 otherwise.\n\
+    | '==' and '!=' need values on both sides. Otherwise '=' is the answer.\n\n\
+    Boolean: 'and', 'or', 'not'\n\
+    | Values are true when they are not 0 (x and y) (x or y) (not x).\n\
+    | Words must be separated from variable names.".to_string()
 }
 
-// Prints variable information.
-fn print_variable_info() {
-    println!("Variable Info:\n\n\
+// Variable information.
+fn variable_info() -> String {
+    "Variable Info:\n\n\
     Pi: 'p'\n\
-    | Interpreted as 3.14159265359.\n\
+    | Interpreted as 3.141592653589793, the closest float to pi.\n\
     | Cannot be mutated.\n\n\
     Euler's Number: 'e'\n\
-    | Interpreted as 2.71828182845.\n\
+    | Interpreted as 2.718281828459045, the closest float to e.\n\
     | Cannot be mutated.\n\n\
     Answer: '='\n\
     | Interpreted as the most recent successful result value.\n\
     | Has a value of 0 if no previous answer exists or if /reset has been called.\n\
     | '=+1;=*5;=^2' will calculate a result of 25.\n\
     | Cannot be mutated.\n\n\
+    Library Constants: '@tau', '@phys.c', ...\n\
+    | Named constants of the math and phys namespaces. Physical constants\n\
+    |    are in SI units. '/constants' lists them with their units.\n\
+    | '@name' works when only one namespace has the name.\n\n\
+    Results: '$1', '$2', ..., '$$'\n\
+    | Interpreted as the numbered results listed by /history.\n\
+    | '$$' is the most recent result, like '='.\n\
+    | '2*3;4*5;$1+$2' will calculate a result of 26.\n\
+    | '/history clear' and /reset remove all results.\n\n\
     Mutable Variables: 'i', 'j', 'k', 'l', 'm', 'n', 'o'\n\
     | These variables can be changed to any value specified by the user\n\
     | Their default value is 1.\n\
@@ -203,12 +515,12 @@ fn print_variable_info() {
     | To change i to -41, enter:\n\
     |    '_i(-41)'\n\
     | To change n to whatever '5R41' evaluates to, enter:\n\
-    |    '_n(5R41)'");
+    |    '_n(5R41)'".to_string()
 }
 
-// Prints complex operator information.
-fn print_complex_operators() {
-    println!("Complex Operators:\n\n\
+// Complex operator information.
+fn complex_operator_info() -> String {
+    "Complex Operators:\n\n\
     General Info:\n\
     | Complex operators are entered in the form:\n\
     |    'capital letter[comma separated values]'\n\
@@ -219,11 +531,19 @@ fn print_complex_operators() {
     Summation: 'S'\n\
     | Computes summation from start value to upper limit of equation:\n\
     |    'S[start, upper limit, equation]'\n\
-    | Equation can include the variable 'x'.\n\n\
+    |    'S[start, upper limit, step, equation]'\n\
+    | Equation can include the variable 'x'. The step is 1, or -1 when\n\
+    |    counting down, and bounds may be fractional.\n\
+    | An upper limit of 'inf' continues until the terms converge:\n\
+    |    'S[0, inf, 1/x!]'\n\
+    | The start may name another variable, which nested operators can use:\n\
+    |    'S[k=1, 10, k*S[j=1, k, j]]'\n\n\
     Product: 'P'\n\
     | Computes the product from start value to upper limit of equation:\n\
     |    'P[start, upper limit, equation]'\n\
-    | Equation can include the variable 'x'.\n\n\
+    |    'P[start, upper limit, step, equation]'\n\
+    | Equation can include the variable 'x'. Named variables, steps, and\n\
+    |    'inf' work as for 'S'.\n\n\
     Mean: 'M'\n\
     | Computes the mean of entered values:\n\
     |    'M[value, value, value, value, ...]'\n\n\
@@ -234,14 +554,59 @@ fn print_complex_operators() {
     | Computes the quadratic formula results of entered a, b, and c values:\n\
     |    'Q[a, b, c]'\n\
     | If two real roots exist, both will be printed to the screen, but only \n\
-    |    the second root will be treated as the result.");
+    |    the second root will be treated as the result.\n\n\
+    Number Theory: 'gcd', 'lcm', 'isprime', 'factor', 'modpow', 'modinv', 'ncr', 'npr'\n\
+    | Named complex operators computed exactly on integers of any size:\n\
+    |    'gcd[value, value, ...]' and 'lcm[value, value, ...]'\n\
+    |    'isprime[value]' returns 1 if value is prime and 0 otherwise. It is\n\
+    |       exact below 3.2E23 and uses the Baillie-PSW test above\n\
+    |    'factor[value]' returns the list of prime factors of value\n\
+    |    'modpow[base, exponent, modulus]' and 'modinv[value, modulus]'\n\
+    |    'ncr[n, r]' and 'npr[n, r]' count combinations and permutations\n\
+    | Values must evaluate to integers.\n\n\
+    Conditional: 'if'\n\
+    | Computes 'if[condition, value if true, value if false]'.\n\
+    | Only the chosen value is evaluated.\n\n\
+    Vectors and Matrices: '[...]'\n\
+    | Brackets without an operator build a vector or a matrix of rows:\n\
+    |    '[1, 2, 3]' and '[[1, 2], [3, 4]]'\n\
+    | '+' and '-' work element by element, and numbers apply to every element.\n\
+    | '*' multiplies matrices and vectors. Two vectors give their dot product.\n\
+    | '^' raises a square matrix to an integer power.\n\
+    |    'transpose[matrix]', 'det[matrix]', 'inv[matrix]', 'rank[matrix]'\n\
+    |    'solve[A, b]' solves Ax = b for a square matrix A and a vector b\n\
+    |    'eig[matrix]' returns the real eigenvalues, largest first\n\n\
+    Finance: 'pv', 'fv', 'pmt', 'nper', 'rate', 'npv', 'irr', 'amort'\n\
+    | Money received is positive and money paid is negative.\n\
+    |    'pv[rate, periods, payment, future value, due]'\n\
+    |    'fv[rate, periods, payment, present value, due]'\n\
+    |    'pmt[rate, periods, present value, future value, due]'\n\
+    |    'nper[rate, payment, present value, future value, due]'\n\
+    |    'rate[periods, payment, present value, future value, due]'\n\
+    | The last two values may be left out. 'due' is 1 for payments at the\n\
+    |    start of each period. Lists compute a result for each element.\n\
+    |    'npv[rate, cash flows]' and 'irr[cash flows]'\n\
+    |    'amort[rate, periods, present value]' returns a payment schedule\n\n\
+    Random Numbers: 'rand', 'randn', 'randint'\n\
+    | 'rand[]' or 'rand[low, high]' is a uniform draw, 'randn[]' or\n\
+    |    'randn[mean, standard deviation]' a normal draw, and\n\
+    |    'randint[low, high]' a whole number from low to high.\n\
+    | Each term of 'S[1, 1000, rand[]]' gets its own draw.\n\
+    | '/set seed 42' repeats the same draws. '/set seed random' undoes it.\n\n\
+    Distributions: 'normpdf', 'normcdf', 'norminv', 'tpdf', 'tcdf', 'tinv',\n\
+    |    'binompdf', 'binomcdf', 'binominv', 'poissonpdf', 'poissoncdf', 'poissoninv'\n\
+    | 'normcdf[x, mean, standard deviation]' (or 'normcdf[x]'),\n\
+    |    'tcdf[x, degrees of freedom]', 'binomcdf[k, trials, success probability]',\n\
+    |    and 'poissoncdf[k, mean]'. 'pdf' and 'inv' take the same values.\n\
+    | 'inv' functions take a probability in place of x.".to_string()
 }
 
-// Prints order of operations.
-fn print_operation_order() {
-    println!("Rusty Calculator's order of operations (Operators\n\
+// Order of operations.
+fn operation_order() -> String {
+    "Rusty Calculator's order of operations (Operators\n\
         in between [] have the same precedence and will be evaluated\n\
         from left to right):\n\n\
-        !, ^, ~ (negation), [R, L, N, H, A], [*, /, %, #, //], [%%, \\], [+, -]\n\n\
-        Complex operators are evaluated to numerical values before order of operations are applied.");
+        !, ^, [~ (negation), ±], [R, L, N, H, A], [*, /, %, #, //], [%%, \\], [+, -],\n\
+        [<, <=, ==, !=, >, >=], not, and, or\n\n\
+        Complex operators are evaluated to numerical values before order of operations are applied.".to_string()
 }
\ No newline at end of file
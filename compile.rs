@@ -0,0 +1,279 @@
+use crate::evaluate::{factorial_operand, float_operation};
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::tokenize::tokenize;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::f64::consts;
+
+// Largest number of values a compiled expression may hold on its stack while it is evaluated.
+pub const MAX_STACK: usize = 64;
+
+const BINARY_OPERATORS: [&str; 19] = ["+", "-", "*", "/", "^", "%", "#", "\\", "R", "L", "H",
+                                      "<", "<=", "==", "!=", ">", ">=", "and", "or"];
+const UNARY_OPERATORS: [&str; 4] = ["!", "~", "A", "not"];
+
+// One step of a compiled expression, in postfix order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Instruction {
+    Number(f64),
+    Variable(usize),
+    Unary(&'static str),
+    Binary(&'static str),
+}
+
+// An equation tokenized and converted to postfix once, so it can be evaluated many times with different
+// variable values. Values are computed as floats, so results match the calculator's within rounding.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompiledExpr {
+    instructions: Vec<Instruction>,
+    variables: Vec<char>,
+}
+
+// Values of the variables a compiled expression reads, by lowercase letter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Env {
+    values: [Option<f64>; 26],
+}
+
+impl Env {
+    pub fn new() -> Env {
+        Env::default()
+    }
+
+    // Sets a variable. Variables are lowercase letters other than the constants p and e.
+    pub fn set(&mut self, name: char, value: f64) -> Result<(), String> {
+        let index = variable_index(name).ok_or_else(|| {
+            format!("{} is not a variable. Variables are lowercase letters other than p and e", name)
+        })?;
+        self.values[index] = Some(value);
+        Ok(())
+    }
+
+    pub fn get(&self, name: char) -> Option<f64> {
+        variable_index(name).and_then(|index| self.values[index])
+    }
+}
+
+// Compiles an equation of numbers, operators, and variables. Complex operators are computed while
+// compiling, so they may only use numbers and constants. Lists, dates, uncertain values, and random
+// draws cannot be compiled.
+pub fn compile(equation: &str) -> Result<CompiledExpr, String> {
+    // Each variable is tokenized as its own name, to be looked up when the expression is evaluated.
+    let mut variables: HashMap<char, String> = ('a'..='z').map(|name| (name, name.to_string())).collect();
+    variables.insert('p', consts::PI.to_string());
+    variables.insert('e', consts::E.to_string());
+    let tokens = multiply_variables(tokenize(equation, &variables)?);
+
+    let mut instructions = Vec::new();
+    let mut names = Vec::new();
+    let mut depth = 0;
+    for token in infix_to_postfix(&tokens) {
+        let instruction = instruction(&token)?;
+        let operands = match instruction {
+            Instruction::Number(_) => 0,
+            Instruction::Variable(index) => {
+                let name = (b'a' + index as u8) as char;
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+                0
+            },
+            Instruction::Unary(_) => 1,
+            Instruction::Binary(_) => 2,
+        };
+        if depth < operands {
+            return Err("Not enough operands".to_string());
+        }
+        depth = depth - operands + 1;
+        if depth > MAX_STACK {
+            return Err(format!("Cannot compile an expression holding more than {} values at once", MAX_STACK));
+        }
+        instructions.push(instruction);
+    }
+
+    match depth {
+        0 => Err("Empty expression".to_string()),
+        1 => Ok(CompiledExpr { instructions, variables: names }),
+        _ => Err("Too many operands".to_string()),
+    }
+}
+
+impl CompiledExpr {
+    // Evaluates the expression with the given variable values, without allocating unless it fails.
+    pub fn eval(&self, env: &Env) -> Result<f64, String> {
+        let mut stack = [0.0; MAX_STACK];
+        let mut top = 0;
+
+        // compile checked that each operator has its operands and that the stack holds them.
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Number(value) => {
+                    stack[top] = value;
+                    top += 1;
+                },
+                Instruction::Variable(index) => {
+                    stack[top] = env.values[index]
+                        .ok_or_else(|| format!("{} has no value", (b'a' + index as u8) as char))?;
+                    top += 1;
+                },
+                Instruction::Unary(operator) => stack[top - 1] = unary_operation(operator, stack[top - 1])?,
+                Instruction::Binary(operator) => {
+                    top -= 1;
+                    stack[top - 1] = binary_operation(operator, stack[top - 1], stack[top])?;
+                },
+            }
+        }
+        Ok(stack[0])
+    }
+
+    // The variables the expression reads, in the order they first appear.
+    pub fn variables(&self) -> &[char] {
+        &self.variables
+    }
+}
+
+// Converts a postfix token to an instruction.
+fn instruction(token: &str) -> Result<Instruction, String> {
+    if let Some(operator) = BINARY_OPERATORS.iter().find(|operator| **operator == token) {
+        return Ok(Instruction::Binary(operator));
+    }
+    if let Some(operator) = UNARY_OPERATORS.iter().find(|operator| **operator == token) {
+        return Ok(Instruction::Unary(operator));
+    }
+    if let Some(index) = variable(token) {
+        return Ok(Instruction::Variable(index));
+    }
+    match Value::parse(token) {
+        Some(value @ (Value::Integer(_) | Value::Float(_))) => Ok(Instruction::Number(value.to_f64()?)),
+        _ => Err(format!("{} cannot be compiled. Compiled expressions use numbers and variables", token)),
+    }
+}
+
+// Applies a binary operator the way evaluate does for floats. Comparisons and boolean operators
+// return 1 or 0, and remainders by 0 are errors as they are for integers.
+fn binary_operation(operator: &str, a: f64, b: f64) -> Result<f64, String> {
+    let result = match operator {
+        "<" => a < b,
+        "<=" => a <= b,
+        "==" => a == b,
+        "!=" => a != b,
+        ">" => a > b,
+        ">=" => a >= b,
+        "and" => a != 0.0 && b != 0.0,
+        "or" => a != 0.0 || b != 0.0,
+        "%" | "#" if b == 0.0 => {
+            let error_message = format!("Divide by Zero {} {} {}", a, operator, b);
+            return Err(error_message);
+        },
+        operator => return float_operation(operator, a, b),
+    };
+    Ok(result as u8 as f64)
+}
+
+fn unary_operation(operator: &str, a: f64) -> Result<f64, String> {
+    let result = match operator {
+        "!" => (2..=factorial_operand(a)?).map(f64::from).product(),
+        "~" => -a,
+        "A" => a.abs(),
+        _ => (a == 0.0) as u8 as f64,
+    };
+    Ok(result)
+}
+
+// Inserts '*' after a variable followed by a number, variable, or '(', as tokenize does after numbers.
+// Used for tokens where variables were left as their names.
+pub(crate) fn multiply_variables(tokens: Vec<String>) -> Vec<String> {
+    let mut multiplied: Vec<String> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if multiplied.last().is_some_and(|last| variable(last).is_some())
+            && (token == "(" || variable(&token).is_some() || token.parse::<f64>().is_ok()) {
+            multiplied.push("*".to_string());
+        }
+        multiplied.push(token);
+    }
+    multiplied
+}
+
+// Returns the index of a token naming a variable.
+fn variable(token: &str) -> Option<usize> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(name), None) => variable_index(name),
+        _ => None,
+    }
+}
+
+fn variable_index(name: char) -> Option<usize> {
+    if name.is_ascii_lowercase() && name != 'p' && name != 'e' {
+        Some((name as u8 - b'a') as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate::evaluate;
+
+    // Solves an equation through the string pipeline with the variables set in env.
+    fn solve(equation: &str, env: &Env) -> f64 {
+        let mut variables: HashMap<char, String> = HashMap::new();
+        variables.insert('p', consts::PI.to_string());
+        variables.insert('e', consts::E.to_string());
+        for name in 'a'..='z' {
+            if let Some(value) = env.get(name) {
+                variables.insert(name, value.to_string());
+            }
+        }
+        let tokens = tokenize(equation, &variables).unwrap();
+        evaluate(infix_to_postfix(&tokens)).unwrap().to_f64().unwrap()
+    }
+
+    #[test]
+    fn compiled_expressions_match_the_string_path() {
+        let equations = ["3x^2 + 2x*y - y/4", "R(x^2 + y^2)", "x(y + 1) - 2(x)y", "2pR(x) + Ny",
+                         "xH y % 3", "A(x - y)^2 + 3L(y)", "x < y and not y == 3", "10//x + 50%%y",
+                         "S[1, 4, x^2]x", "-x^3!"];
+        let mut env = Env::new();
+        for (x, y) in [(3.0, 4.0), (0.5, 2.25), (4.0, 3.0)] {
+            env.set('x', x).unwrap();
+            env.set('y', y).unwrap();
+            for equation in equations {
+                let compiled = compile(equation).unwrap().eval(&env).unwrap();
+                let expected = solve(equation, &env);
+                assert!((compiled - expected).abs() <= 1E-9 * expected.abs(), "{}: {} != {}",
+                        equation, compiled, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn variables_are_read_when_evaluated() {
+        let compiled = compile("b*a + b").unwrap();
+        assert_eq!(compiled.variables(), ['b', 'a']);
+        let mut env = Env::new();
+        env.set('a', 2.0).unwrap();
+        assert!(compiled.eval(&env).is_err());
+        env.set('b', 3.0).unwrap();
+        assert_eq!(compiled.eval(&env), Ok(9.0));
+        env.set('b', -1.0).unwrap();
+        assert_eq!(compiled.eval(&env), Ok(-3.0));
+        assert!(env.set('p', 1.0).is_err());
+        assert!(env.set('X', 1.0).is_err());
+    }
+
+    #[test]
+    fn invalid_expressions_are_errors() {
+        let env = Env::new();
+        assert!(compile("[1, 2] + 1").is_err());
+        assert!(compile("{2024-01-01}").is_err());
+        assert!(compile("1±0.1").is_err());
+        assert!(compile("rand[]").is_err());
+        assert!(compile("").is_err());
+        assert!(compile(&format!("{}1{}", "1-(".repeat(MAX_STACK), ")".repeat(MAX_STACK))).is_err());
+        assert!(compile("1/0").unwrap().eval(&env).is_err());
+        assert!(compile("5 % 0").unwrap().eval(&env).is_err());
+        assert!(compile("2.5!").unwrap().eval(&env).is_err());
+    }
+}
@@ -1,21 +1,75 @@
 use crate::infix_to_postfix::infix_to_postfix;
 use crate::evaluate::evaluate;
+use crate::finance;
+use crate::matrix;
+use crate::number_theory;
+use crate::probability;
+
+// Named complexity types, entered as 'name[comma separated values]'.
+pub const FUNCTIONS: [&str; 38] = ["gcd", "lcm", "isprime", "factor", "modpow", "modinv", "ncr", "npr", "if",
+                                   "transpose", "det", "inv", "rank", "solve", "eig",
+                                   "pv", "fv", "pmt", "nper", "rate", "npv", "irr", "amort",
+                                   "rand", "randn", "randint", "normpdf", "normcdf", "norminv", "tpdf", "tcdf",
+                                   "tinv", "binompdf", "binomcdf", "binominv", "poissonpdf", "poissoncdf",
+                                   "poissoninv"];
 
 // Calls correct complexity function.
-pub fn complex_evaluate<'a>(tokens: &'a Vec<String>, complexity_type: &char) -> Result<String, &'a str> {
+pub fn complex_evaluate<'a>(tokens: &'a Vec<String>, complexity_type: &str) -> Result<String, &'a str> {
     match complexity_type {
-        'S' => summation(&tokens),
-        'P' => product(&tokens),
-        'M' => mean(&tokens),
-        'O' => std_deviation(&tokens),
-        'Q' => quadratic(&tokens),
+        "M" => mean(tokens),
+        "O" => std_deviation(tokens),
+        "Q" => quadratic(tokens),
+        "gcd" => number_theory::gcd(tokens),
+        "lcm" => number_theory::lcm(tokens),
+        "isprime" => number_theory::is_prime(tokens),
+        "factor" => number_theory::factor(tokens),
+        "modpow" => number_theory::modpow(tokens),
+        "modinv" => number_theory::modinv(tokens),
+        "ncr" => number_theory::combinations(tokens),
+        "npr" => number_theory::permutations(tokens),
+        "if" => conditional(tokens),
+        "[" => matrix::list(tokens),
+        "transpose" => matrix::transpose(tokens),
+        "det" => matrix::determinant(tokens),
+        "inv" => matrix::inverse_matrix(tokens),
+        "rank" => matrix::rank(tokens),
+        "solve" => matrix::solve(tokens),
+        "eig" => matrix::eigenvalues(tokens),
+        "pv" => finance::pv(tokens),
+        "fv" => finance::fv(tokens),
+        "pmt" => finance::pmt(tokens),
+        "nper" => finance::nper(tokens),
+        "rate" => finance::rate(tokens),
+        "npv" => finance::npv(tokens),
+        "irr" => finance::irr(tokens),
+        "amort" => finance::amortization(tokens),
+        "rand" => probability::uniform(tokens),
+        "randn" => probability::normal(tokens),
+        "randint" => probability::integer(tokens),
+        "normpdf" => probability::normal_pdf(tokens),
+        "normcdf" => probability::normal_cdf(tokens),
+        "norminv" => probability::normal_inverse(tokens),
+        "tpdf" => probability::t_pdf(tokens),
+        "tcdf" => probability::t_cdf(tokens),
+        "tinv" => probability::t_inverse(tokens),
+        "binompdf" => probability::binomial_pdf(tokens),
+        "binomcdf" => probability::binomial_cdf(tokens),
+        "binominv" => probability::binomial_inverse(tokens),
+        "poissonpdf" => probability::poisson_pdf(tokens),
+        "poissoncdf" => probability::poisson_cdf(tokens),
+        "poissoninv" => probability::poisson_inverse(tokens),
         _ => Err("Unknown complexity type"),
     }
 }
 
+// Evaluates an infix token vector to a float.
+fn evaluate_number(tokens: &Vec<String>) -> Result<f64, String> {
+    evaluate(infix_to_postfix(tokens))?.to_f64()
+}
+
 // Separates a vector into multiple vectors based on commas.
 // length_limit of 0 will separate indefinitely.
-fn separate_vector(tokens: &Vec<String>, length_limit: usize) -> Vec<Vec<String>> {
+pub fn separate_vector(tokens: &Vec<String>, length_limit: usize) -> Vec<Vec<String>> {
     let mut separated_tokens: Vec<Vec<String>> = vec![Vec::new()];
 
     for token in tokens {
@@ -46,87 +100,9 @@ fn separate_vector(tokens: &Vec<String>, length_limit: usize) -> Vec<Vec<String>
     separated_tokens
 }
 
-// Compute the summation of given tokens as [start,upper limit,equation].
-fn summation(tokens: &Vec<String>) -> Result<String, &str> {
-    let separated_tokens = separate_vector(&tokens, 3);
-    
-    match separated_tokens.len() {
-        0 => return Err("Missing summation start, upper limit, and equation"),
-        1 => return Err("Missing summation upper limit and equation"),
-        2 => return Err("Missing summation equation"),
-        _ => {}
-    }
-
-    let start = match evaluate(infix_to_postfix(&separated_tokens[0])) {
-        Ok(result) => result as i64,
-        Err(_) => return Err("Could not evaluate summation start"),
-    };
-
-    let upper_limit = match evaluate(infix_to_postfix(&separated_tokens[1])) {
-        Ok(result) => result as i64,
-        Err(_) => return Err("Could not evaluate summation upper limit"),
-    };
-
-    let equation = separated_tokens[2].clone();
-    let mut summation_result = 0.0;
-    for i in start..=upper_limit {
-        let mut equation_tokens = equation.clone();
-        for token in equation_tokens.iter_mut() {
-            if *token == "x" {
-                *token = i.to_string();
-            }
-        }
-
-        match evaluate(infix_to_postfix(&equation_tokens)) {
-            Ok(result) => summation_result += result,
-            Err(_) => return Err("Could not evaluate summation equation"),
-        }
-    }
-    Ok(summation_result.to_string())
-}
-
-// Compute the product of given tokens as [start,upper limit,equation].
-fn product(tokens: &Vec<String>) -> Result<String, &str> {
-    let separated_tokens = separate_vector(&tokens, 3);
-
-    match separated_tokens.len() {
-        0 => return Err("Missing product start, upper limit, and equation"),
-        1 => return Err("Missing product upper limit and equation"),
-        2 => return Err("Missing product equation"),
-        _ => {}
-    }
-
-    let start = match evaluate(infix_to_postfix(&separated_tokens[0])) {
-        Ok(result) => result as i64,
-        Err(_) => return Err("Could not evaluate product start"),
-    };
-
-    let upper_limit = match evaluate(infix_to_postfix(&separated_tokens[1])) {
-        Ok(result) => result as i64,
-        Err(_) => return Err("Could not evaluate product upper limit"),
-    };
-
-    let equation = separated_tokens[2].clone();
-    let mut product_result = 1.0;
-    for i in start..=upper_limit {
-        let mut equation_tokens = equation.clone();
-        for token in equation_tokens.iter_mut() {
-            if *token == "x" {
-                *token = i.to_string();
-            }
-        }
-
-        match evaluate(infix_to_postfix(&equation_tokens)) {
-            Ok(result) => product_result *= result,
-            Err(_) => return Err("Could not evaluate product equation"),
-        }
-    }
-    Ok(product_result.to_string())
-}
-
 // Compute the average of given tokens as [value,value,...].
 fn mean(tokens: &Vec<String>) -> Result<String, &str> {
-    let separated_tokens = separate_vector(&tokens, 0);
+    let separated_tokens = separate_vector(tokens, 0);
 
     let total_values = separated_tokens.len();
     if total_values == 0 {
@@ -134,7 +110,7 @@ fn mean(tokens: &Vec<String>) -> Result<String, &str> {
     }
 
     let sum = separated_tokens.iter().try_fold(0.0, |acc, equation_tokens| {
-        match evaluate(infix_to_postfix(&equation_tokens)) {
+        match evaluate_number(equation_tokens) {
             Ok(result) => Ok(acc + result),
             Err(_) => Err("Could not evaluate average value equation"),
         }
@@ -146,7 +122,7 @@ fn mean(tokens: &Vec<String>) -> Result<String, &str> {
 
 // Compute the standard deviation of given tokens as [value,value,...].
 fn std_deviation(tokens: &Vec<String>) -> Result<String, &str> {
-    let separated_tokens = separate_vector(&tokens, 0);
+    let separated_tokens = separate_vector(tokens, 0);
 
     let total_values = separated_tokens.len() as f64;
     if total_values == 0.0 {
@@ -155,7 +131,7 @@ fn std_deviation(tokens: &Vec<String>) -> Result<String, &str> {
 
     let mut values: Vec<f64> = Vec::new();
     let sum = separated_tokens.iter().try_fold(0.0, |acc, equation_tokens| {
-        match evaluate(infix_to_postfix(&equation_tokens)) {
+        match evaluate_number(equation_tokens) {
             Ok(result) => {
                 values.push(result);
                 Ok(acc + result)
@@ -170,9 +146,27 @@ fn std_deviation(tokens: &Vec<String>) -> Result<String, &str> {
     Ok(standard_deviation.to_string())
 }
 
+// Evaluates only the chosen value of given tokens as [condition,value if true,value if false].
+fn conditional(tokens: &Vec<String>) -> Result<String, &str> {
+    let separated_tokens = separate_vector(tokens, 3);
+    if separated_tokens.len() != 3 {
+        return Err("Expected if condition, value if true, and value if false");
+    }
+
+    let condition = match evaluate(infix_to_postfix(&separated_tokens[0])) {
+        Ok(result) => result.is_true().map_err(|_| "If condition must be a number")?,
+        Err(_) => return Err("Could not evaluate if condition"),
+    };
+    let chosen = if condition { &separated_tokens[1] } else { &separated_tokens[2] };
+    match evaluate(infix_to_postfix(chosen)) {
+        Ok(result) => Ok(result.to_string()),
+        Err(_) => Err("Could not evaluate if value"),
+    }
+}
+
 // Computes quadratic formula of given tokens as [a,b,c].
 fn quadratic(tokens: &Vec<String>) -> Result<String, &str> {
-    let separated_tokens = separate_vector(&tokens, 3);
+    let separated_tokens = separate_vector(tokens, 3);
 
     match separated_tokens.len() {
         0 => return Err("Missing quadratic a, b, and c values"),
@@ -181,18 +175,18 @@ fn quadratic(tokens: &Vec<String>) -> Result<String, &str> {
         _ => {}
     }
 
-    let a = match evaluate(infix_to_postfix(&separated_tokens[0])) {
-        Ok(result) => result as f64,
+    let a = match evaluate_number(&separated_tokens[0]) {
+        Ok(result) => result,
         Err(_) => return Err("Could not evaluate quadratic a value"),
     };
 
-    let b = match evaluate(infix_to_postfix(&separated_tokens[1])) {
-        Ok(result) => result as f64,
+    let b = match evaluate_number(&separated_tokens[1]) {
+        Ok(result) => result,
         Err(_) => return Err("Could not evaluate quadratic b value"),
     };
 
-    let c = match evaluate(infix_to_postfix(&separated_tokens[2])) {
-        Ok(result) => result as f64,
+    let c = match evaluate_number(&separated_tokens[2]) {
+        Ok(result) => result,
         Err(_) => return Err("Could not evaluate quadratic c value"),
     };
 
@@ -0,0 +1,221 @@
+use crate::command::{check_mutable, COMMANDS};
+use crate::evaluate::evaluate;
+use crate::function::{parse_definition, Function};
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::tokenize::tokenize_with_context;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+// Sections a config file may contain.
+const SECTIONS: [&str; 5] = ["settings", "variables", "constants", "functions", "aliases"];
+
+// Startup defaults read from a TOML config file:
+//
+//     [settings]
+//     precision = 4
+//     [variables]
+//     i = 2
+//     [constants]
+//     c = 299792458
+//     g = "9.80665"
+//     [functions]
+//     "hyp(i, j)" = "R(i^2 + j^2)"
+//     [aliases]
+//     q = "/x"
+//
+// Values of variables and constants may be numbers or equations, solved in the order they are written.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    settings: Vec<(String, String)>,
+    variables: Vec<(char, String)>,
+    constants: Vec<(char, String)>,
+    functions: Vec<(String, Function)>,
+    pub aliases: HashMap<String, String>,
+}
+
+// Returns the path of the config file, following the XDG base directory spec.
+pub fn default_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("rusty_calculator").join("config.toml"))
+}
+
+// Reads a config file. Every entry is checked, except that equations are only solved when applied.
+pub fn load(path: &Path) -> Result<Config, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Parses the contents of a config file.
+pub fn parse(contents: &str) -> Result<Config, String> {
+    let table = contents.parse::<Table>().map_err(|e| {
+        let line = e.span().map_or(1, |span| contents[..span.start].matches('\n').count() + 1);
+        format!("Line {}: {}", line, e.message())
+    })?;
+    let mut config = Config::default();
+    // Settings are checked in order, since whether a precision is valid depends on the notation.
+    let mut settings = Settings::default();
+
+    for (section, entries) in &table {
+        if !SECTIONS.contains(&section.as_str()) {
+            return Err(format!("[{}] is not a section. Try {}", section, SECTIONS.join(", ")));
+        }
+        let entries = entries.as_table().ok_or_else(|| format!("{} must be a [{}] section", section, section))?;
+        for (key, value) in entries {
+            let entry = |e: String| format!("{}.{}: {}", section, key, e);
+            match section.as_str() {
+                "settings" => {
+                    let value = match value {
+                        Value::String(text) => text.clone(),
+                        Value::Integer(integer) => integer.to_string(),
+                        Value::Boolean(boolean) => boolean.to_string(),
+                        _ => return Err(entry("Expected text, a whole number, or true or false".to_string())),
+                    };
+                    settings.set(key, &value).map_err(entry)?;
+                    config.settings.push((key.clone(), value));
+                },
+                "variables" => {
+                    let variable = single_char(key).ok_or_else(|| entry("Expected a variable name".to_string()))?;
+                    check_mutable(variable).map_err(entry)?;
+                    config.variables.push((variable, equation(value).map_err(entry)?));
+                },
+                "constants" => {
+                    let constant = single_char(key).filter(|c| is_constant_name(*c)).ok_or_else(|| {
+                        entry("Constant names are a lowercase letter other than i to o, p, e, and x".to_string())
+                    })?;
+                    config.constants.push((constant, equation(value).map_err(entry)?));
+                },
+                "functions" => {
+                    let body = value.as_str().ok_or_else(|| entry("Expected an equation in quotes".to_string()))?;
+                    let (name, function) = parse_definition(&format!("{} = {}", key, body)).map_err(entry)?;
+                    config.functions.push((name, function));
+                },
+                _ => {
+                    let name = format!("/{}", key.trim_start_matches('/'));
+                    let command = value.as_str()
+                        .filter(|command| command.split(' ').next().is_some_and(|name| COMMANDS.contains(&name)))
+                        .ok_or_else(|| entry("Expected a command in quotes, like \"/variables\"".to_string()))?;
+                    if COMMANDS.contains(&name.as_str()) {
+                        return Err(entry(format!("{} is already a command", name)));
+                    }
+                    config.aliases.insert(name, command.to_string());
+                },
+            }
+        }
+    }
+    Ok(config)
+}
+
+// Applies settings, variables, constants, and functions to a session and keeps the config for
+// aliases and /reset. Constants of a previously applied config are removed first.
+// Stops at the first equation that cannot be solved.
+pub fn apply(config: Config, session: &mut Session) -> Result<(), String> {
+    for (name, _) in &session.config.constants {
+        session.variables.remove(name);
+    }
+    for (name, value) in &config.settings {
+        session.settings.set(name, value)?;
+    }
+    for (name, function) in &config.functions {
+        session.functions.insert(name.clone(), function.clone());
+    }
+    for (section, entries) in [("constants", &config.constants), ("variables", &config.variables)] {
+        for (name, equation) in entries {
+            let tokens = tokenize_with_context(equation, session.context());
+            let value = tokens.and_then(|tokens| evaluate(infix_to_postfix(&tokens)))
+                .map_err(|e| format!("{}.{}: Unable to solve {}: {}", section, name, equation, e))?;
+            session.variables.insert(*name, value.to_string());
+        }
+    }
+    session.config = config;
+    Ok(())
+}
+
+// Reads the config file at the default path, if there is one, and applies it to a session.
+pub fn apply_default(session: &mut Session) -> Result<(), String> {
+    match default_path() {
+        Some(path) if path.exists() => apply(load(&path)?, session),
+        _ => Ok(()),
+    }
+}
+
+// Constants cannot replace user variables, built in constants, or the x of summations and products.
+fn is_constant_name(c: char) -> bool {
+    c.is_ascii_lowercase() && !matches!(c, 'i'..='o' | 'p' | 'e' | 'x')
+}
+
+// Returns the only character of text.
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    chars.next().filter(|_| chars.next().is_none())
+}
+
+// Reads a number or an equation in quotes.
+fn equation(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Integer(integer) => Ok(integer.to_string()),
+        Value::Float(float) => Ok(float.to_string()),
+        Value::String(text) => Ok(text.clone()),
+        _ => Err("Expected a number or an equation in quotes".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_every_section() {
+        let config = parse("\
+            [settings]\n\
+            notation = \"fixed\"\n\
+            precision = 2\n\
+            reveal = true\n\
+            [constants]\n\
+            c = 299792458\n\
+            g = \"c/2\"\n\
+            [variables]\n\
+            i = \"g*2\"\n\
+            [functions]\n\
+            \"twice(i)\" = \"i*2\"\n\
+            [aliases]\n\
+            q = \"/x\"").unwrap();
+        let mut session = Session::new();
+        apply(config, &mut session).unwrap();
+
+        assert_eq!(session.settings.format.notation_name(), "fixed 2");
+        assert!(session.settings.reveal);
+        assert_eq!(session.variables[&'g'], "149896229");
+        assert_eq!(session.variables[&'i'], "299792458");
+        assert!(session.functions.contains_key("twice"));
+        assert_eq!(session.config.aliases["/q"], "/x");
+    }
+
+    #[test]
+    fn malformed_entries_are_errors() {
+        let error = |contents: &str| parse(contents).unwrap_err();
+        assert!(error("[settings]\nprecision = 1\n[settings\n").starts_with("Line 3: "));
+        assert_eq!(error("[colors]\n"), "[colors] is not a section. Try settings, variables, constants, \
+                                          functions, aliases");
+        assert!(error("[settings]\nprecision = 101").starts_with("settings.precision: 101 is not"));
+        assert!(error("[settings]\ntheme = \"dark\"").starts_with("settings.theme: theme is not a setting"));
+        assert!(error("[variables]\np = 3").starts_with("variables.p: p is not a mutable variable"));
+        assert!(error("[constants]\ni = 3").starts_with("constants.i: Constant names"));
+        assert!(error("[constants]\nc = [1, 2]").starts_with("constants.c: Expected a number"));
+        assert!(error("[functions]\n\"f(i)\" = \"i\"").starts_with("functions.f(i): f is not a valid"));
+        assert!(error("[aliases]\nhelp = \"/x\"").starts_with("aliases.help: /help is already a command"));
+        assert!(error("[aliases]\nq = \"x\"").starts_with("aliases.q: Expected a command"));
+        assert!(error("[aliases]\nq = \"/w\"\nw = \"/q\"").starts_with("aliases.q: Expected a command"));
+
+        let config = parse("[constants]\nc = \"1/0\"").unwrap();
+        assert!(apply(config, &mut Session::new()).unwrap_err().starts_with("constants.c: Unable to solve 1/0"));
+    }
+}
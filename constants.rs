@@ -0,0 +1,79 @@
+use std::f64::consts;
+
+// A named constant of the library, entered as '@name' or '@namespace.name'.
+// Physical constants are in SI units, using the CODATA 2018 values.
+pub struct Constant {
+    pub namespace: &'static str,
+    pub name: &'static str,
+    pub value: f64,
+    pub unit: &'static str,
+    pub description: &'static str,
+}
+
+const fn constant(namespace: &'static str, name: &'static str, value: f64, unit: &'static str,
+                  description: &'static str) -> Constant {
+    Constant { namespace, name, value, unit, description }
+}
+
+// Constants listed by /constants, grouped by namespace.
+pub const CONSTANTS: [Constant; 22] = [
+    constant("math", "pi", consts::PI, "", "ratio of a circle's circumference to its diameter"),
+    constant("math", "tau", consts::TAU, "", "ratio of a circle's circumference to its radius"),
+    constant("math", "e", consts::E, "", "base of the natural logarithm"),
+    constant("math", "phi", 1.618033988749895, "", "golden ratio"),
+    constant("math", "sqrt2", consts::SQRT_2, "", "square root of 2"),
+    constant("math", "sqrt3", 1.7320508075688772, "", "square root of 3"),
+    constant("math", "ln2", consts::LN_2, "", "natural logarithm of 2"),
+    constant("math", "ln10", consts::LN_10, "", "natural logarithm of 10"),
+    constant("math", "gamma", 0.5772156649015329, "", "Euler-Mascheroni constant"),
+    constant("phys", "c", 299792458.0, "m/s", "speed of light in vacuum"),
+    constant("phys", "G", 6.6743E-11, "m^3/(kg s^2)", "Newtonian constant of gravitation"),
+    constant("phys", "g_n", 9.80665, "m/s^2", "standard acceleration of gravity"),
+    constant("phys", "h", 6.62607015E-34, "J s", "Planck constant"),
+    constant("phys", "hbar", 1.054571817E-34, "J s", "reduced Planck constant"),
+    constant("phys", "k_B", 1.380649E-23, "J/K", "Boltzmann constant"),
+    constant("phys", "N_A", 6.02214076E23, "1/mol", "Avogadro constant"),
+    constant("phys", "R", 8.314462618, "J/(mol K)", "molar gas constant"),
+    constant("phys", "q_e", 1.602176634E-19, "C", "elementary charge"),
+    constant("phys", "m_e", 9.1093837015E-31, "kg", "electron mass"),
+    constant("phys", "m_p", 1.67262192369E-27, "kg", "proton mass"),
+    constant("phys", "eps_0", 8.8541878128E-12, "F/m", "vacuum electric permittivity"),
+    constant("phys", "mu_0", 1.25663706212E-6, "N/A^2", "vacuum magnetic permeability"),
+];
+
+// Finds a constant by 'name' or 'namespace.name'. Names without a namespace must be unique.
+pub fn find(name: &str) -> Result<&'static Constant, String> {
+    let matches: Vec<&Constant> = match name.split_once('.') {
+        Some((namespace, name)) => CONSTANTS.iter()
+            .filter(|constant| constant.namespace == namespace && constant.name == name)
+            .collect(),
+        None => CONSTANTS.iter().filter(|constant| constant.name == name).collect(),
+    };
+    match matches[..] {
+        [constant] => Ok(constant),
+        [] => Err(format!("@{} is not a constant. Try /constants", name)),
+        _ => Err(format!("@{} is in more than one namespace. Try @namespace.{}", name, name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_constants_with_and_without_namespaces() {
+        assert_eq!(find("tau").unwrap().value, 2.0 * consts::PI);
+        assert_eq!(find("phys.c").unwrap().value, 299792458.0);
+        assert_eq!(find("k_B").unwrap().unit, "J/K");
+        assert!(find("math.c").is_err());
+        assert!(find("light").is_err());
+        assert!(find("").is_err());
+    }
+
+    #[test]
+    fn names_are_unique() {
+        for (index, constant) in CONSTANTS.iter().enumerate() {
+            assert!(CONSTANTS[..index].iter().all(|other| other.name != constant.name), "{}", constant.name);
+        }
+    }
+}
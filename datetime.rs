@@ -0,0 +1,218 @@
+use crate::value::Value;
+use jiff::fmt::friendly::{Designator, Direction, Spacing, SpanPrinter};
+use jiff::tz::TimeZone;
+use jiff::{civil, SignedDuration, Span, SpanRelativeTo, SpanRound, Timestamp, Unit, Zoned};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::cmp::Ordering;
+
+// Length of a day in durations. Days are always 24 hours long when measured or divided, but whole
+// days are added to dates on the calendar, keeping the time of day across daylight saving changes.
+const DAY: SignedDuration = SignedDuration::from_hours(24);
+
+// Parses the contents of a date or duration literal, entered in braces as in '{2024-03-01}',
+// '{2024-03-01 14:30}', '{now}', '{today}', or '{90 days}'.
+// Dates without a time zone are in the local time zone, read from the system time zone database.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let text = text.trim();
+    let date = match text {
+        "now" => Some(Zoned::now()),
+        "today" => Zoned::now().start_of_day().ok(),
+        _ => text.parse::<Zoned>().ok()
+            .or_else(|| text.parse::<Timestamp>().ok().map(|timestamp| timestamp.to_zoned(TimeZone::system())))
+            .or_else(|| text.parse::<civil::DateTime>().ok()?.to_zoned(TimeZone::system()).ok()),
+    };
+    if let Some(date) = date {
+        return Ok(Value::Date(date));
+    }
+
+    let span = text.parse::<Span>().map_err(|_| {
+        format!("{} is not a date or duration. Try {{2024-03-01}}, {{2024-03-01 14:30}}, {{now}}, or {{90 days}}", text)
+    })?;
+    span_duration(span).map(Value::Duration)
+}
+
+// Converts a span to a duration. Months and years have no fixed length, so they are not allowed.
+fn span_duration(span: Span) -> Result<SignedDuration, String> {
+    if span.get_years() != 0 || span.get_months() != 0 {
+        return Err("Months and years have no fixed length. Try days or weeks".to_string());
+    }
+    span.to_duration(SpanRelativeTo::days_are_24_hours()).map_err(|_| "Duration is out of range".to_string())
+}
+
+// Writes a duration in days and smaller units, as in '90d 3h' or '90 days 3 hours'.
+fn write_duration(duration: SignedDuration, designator: Designator) -> String {
+    let spacing = match designator {
+        Designator::Verbose => Spacing::BetweenUnitsAndDesignators,
+        _ => Spacing::BetweenUnits,
+    };
+    let printer = SpanPrinter::new().designator(designator).spacing(spacing).direction(Direction::Sign);
+    let span = Span::try_from(duration)
+        .and_then(|span| span.round(SpanRound::new().largest(Unit::Day).days_are_24_hours()));
+    match span {
+        Ok(span) => printer.span_to_string(&span),
+        Err(_) => printer.duration_to_string(&duration),
+    }
+}
+
+// Writes a duration in the form read by parse.
+pub fn duration_text(duration: SignedDuration) -> String {
+    write_duration(duration, Designator::Compact)
+}
+
+// Formats a date for display. The time is left out at midnight.
+pub fn format_date(date: &Zoned) -> String {
+    if date.time() == civil::Time::midnight() {
+        date.strftime("%Y-%m-%d").to_string()
+    } else {
+        date.strftime("%Y-%m-%d %H:%M:%S %Z").to_string()
+    }
+}
+
+// Formats a duration for display, as in '90 days 3 hours'.
+pub fn format_duration(duration: SignedDuration) -> String {
+    write_duration(duration, Designator::Verbose)
+}
+
+// Applies a binary operator where at least one operand is a date or duration.
+// Dates subtract to durations, durations add to dates and each other, durations scale by numbers,
+// and dividing two durations gives their ratio.
+pub fn operation(operator: &str, a: Value, b: Value) -> Result<Value, String> {
+    let out_of_range = || "Date or duration is out of range".to_string();
+    let result = match (operator, &a, &b) {
+        ("-", Value::Date(a), Value::Date(b)) => {
+            // Days are counted on the calendar of the second date.
+            let a = a.with_time_zone(b.time_zone().clone());
+            let span = b.until((Unit::Day, &a)).map_err(|_| out_of_range())?;
+            Value::Duration(span_duration(span)?)
+        },
+        ("+", Value::Date(date), Value::Duration(duration)) | ("+", Value::Duration(duration), Value::Date(date)) => {
+            Value::Date(add(date, *duration).ok_or_else(out_of_range)?)
+        },
+        ("-", Value::Date(date), Value::Duration(duration)) => {
+            Value::Date(add(date, -*duration).ok_or_else(out_of_range)?)
+        },
+        ("+", Value::Duration(a), Value::Duration(b)) => Value::Duration(a.checked_add(*b).ok_or_else(out_of_range)?),
+        ("-", Value::Duration(a), Value::Duration(b)) => Value::Duration(a.checked_sub(*b).ok_or_else(out_of_range)?),
+        ("/", Value::Duration(a), Value::Duration(b)) => {
+            if b.is_zero() {
+                return Err(format!("Divide by Zero {} / {}", a, b));
+            }
+            let (a, b) = (a.as_nanos(), b.as_nanos());
+            if a % b == 0 {
+                Value::Integer(BigInt::from(a / b))
+            } else {
+                Value::Float(a as f64 / b as f64)
+            }
+        },
+        ("*", Value::Duration(duration), number) | ("*", number, Value::Duration(duration))
+            if is_number(number) => Value::Duration(scale(*duration, number, false)?),
+        ("/", Value::Duration(duration), number) if is_number(number) => {
+            Value::Duration(scale(*duration, number, true)?)
+        },
+        _ => return Err(format!("{} is not defined for {} and {}", operator, kind(&a), kind(&b))),
+    };
+    Ok(result)
+}
+
+// Compares two dates or two durations.
+pub fn compare(a: &Value, b: &Value) -> Result<Ordering, String> {
+    match (a, b) {
+        (Value::Date(a), Value::Date(b)) => Ok(a.timestamp().cmp(&b.timestamp())),
+        (Value::Duration(a), Value::Duration(b)) => Ok(a.cmp(b)),
+        _ => Err(format!("Cannot compare {} and {}", kind(a), kind(b))),
+    }
+}
+
+// Adds a duration to a date. Whole days are added on the calendar.
+fn add(date: &Zoned, duration: SignedDuration) -> Option<Zoned> {
+    let nanos = duration.as_nanos();
+    if nanos % DAY.as_nanos() == 0 {
+        let days = i64::try_from(nanos / DAY.as_nanos()).ok()?;
+        date.checked_add(Span::new().try_days(days).ok()?).ok()
+    } else {
+        date.checked_add(duration).ok()
+    }
+}
+
+// Multiplies or divides a duration by a number, to the nearest nanosecond for small integers.
+fn scale(duration: SignedDuration, number: &Value, divide: bool) -> Result<SignedDuration, String> {
+    let factor = number.to_f64()?;
+    if divide && factor == 0.0 {
+        return Err(format!("Divide by Zero {} / {}", duration, number));
+    }
+    let exact = match number {
+        Value::Integer(integer) => integer.to_i32().and_then(|integer| {
+            if divide { duration.checked_div(integer) } else { duration.checked_mul(integer) }
+        }),
+        _ => None,
+    };
+    let seconds = if divide { duration.as_secs_f64() / factor } else { duration.as_secs_f64() * factor };
+    exact.or_else(|| SignedDuration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| "Duration is out of range".to_string())
+}
+
+fn is_number(value: &Value) -> bool {
+    matches!(value, Value::Integer(_) | Value::Float(_))
+}
+
+// Describes the kind of a value for errors.
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Integer(_) | Value::Float(_) => "a number",
+        Value::List(_) => "a list",
+        Value::Date(_) => "a date",
+        Value::Duration(_) => "a duration",
+        Value::Uncertain(_) => "an uncertain value",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> Value {
+        parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_dates_and_durations() {
+        let date = value("2024-03-01T14:30:00+01:00[Europe/Paris]");
+        assert_eq!(date.to_string(), "{2024-03-01T14:30:00+01:00[Europe/Paris]}");
+        assert_eq!(Value::parse(&date.to_string()), Some(date));
+        assert_eq!(value("1h 30m"), Value::Duration(SignedDuration::from_mins(90)));
+        assert_eq!(value("2 weeks"), Value::Duration(DAY * 14));
+        assert_eq!(value("-3 days").to_string(), "{-3d}");
+        assert!(matches!(value("2024-03-01"), Value::Date(date) if date.time() == civil::Time::midnight()));
+        assert!(parse("1 month").unwrap_err().starts_with("Months and years"));
+        assert!(parse("tomorrow").unwrap_err().starts_with("tomorrow is not a date or duration"));
+    }
+
+    #[test]
+    fn computes_with_dates_and_durations() {
+        let paris = |date: &str| value(&format!("{}[Europe/Paris]", date));
+        // Daylight saving time starts in Paris on 2024-03-31.
+        let days = operation("-", paris("2024-04-10T00:00"), paris("2024-03-01T00:00")).unwrap();
+        assert_eq!(days, Value::Duration(DAY * 40));
+        assert_eq!(operation("+", paris("2024-03-30T12:00"), value("1 day")), Ok(paris("2024-03-31T12:00")));
+        assert_eq!(operation("+", paris("2024-03-31T00:00"), value("3h")), Ok(paris("2024-03-31T04:00")));
+        assert_eq!(operation("/", days, value("1 day")), Ok(Value::Integer(BigInt::from(40))));
+        let new_york = value("2024-03-01T10:00[America/New_York]");
+        assert_eq!(operation("-", new_york, paris("2024-03-01T10:00")), Ok(value("6h")));
+        assert_eq!(operation("/", value("90m"), value("1h")), Ok(Value::Float(1.5)));
+        assert_eq!(operation("*", Value::Float(1.5), value("1h")), Ok(value("90m")));
+        assert_eq!(operation("/", value("1h"), Value::Integer(BigInt::from(7))).unwrap().to_string(),
+                   "{8m 34s 285ms 714µs 285ns}");
+        assert!(operation("+", paris("2024-03-01T00:00"), paris("2024-03-01T00:00")).is_err());
+        assert!(operation("/", value("1h"), Value::Integer(BigInt::from(0))).is_err());
+        assert_eq!(compare(&value("1h"), &value("59m")), Ok(Ordering::Greater));
+    }
+
+    #[test]
+    fn formats_for_display() {
+        assert_eq!(format_date(&Zoned::now().start_of_day().unwrap()).len(), "2024-03-01".len());
+        let Value::Date(date) = value("2024-03-01T14:30:00-05:00[America/New_York]") else { panic!() };
+        assert_eq!(format_date(&date), "2024-03-01 14:30:00 EST");
+        assert_eq!(format_duration(SignedDuration::from_hours(75)), "3 days 3 hours");
+    }
+}
@@ -0,0 +1,177 @@
+use crate::command::COMMANDS;
+use crate::settings;
+use crate::complex_evaluate::FUNCTIONS;
+use crate::session::{self, Session};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Config, Context, Editor, Helper};
+use std::path::PathBuf;
+
+// Maximum number of lines kept in the up/down history.
+const HISTORY_SIZE: usize = 1000;
+
+// Line editor with persistent history and tab completion.
+pub struct LineEditor {
+    editor: Editor<CalculatorHelper, DefaultHistory>,
+    history_path: Option<PathBuf>,
+}
+
+// Completes commands and aliases, function names, and variable names.
+#[derive(Default)]
+struct CalculatorHelper {
+    variables: Vec<char>,
+    functions: Vec<String>,
+    aliases: Vec<String>,
+}
+
+impl LineEditor {
+    // Initializes an editor and loads history saved by previous launches.
+    pub fn new() -> Result<LineEditor, String> {
+        let config = Config::builder()
+            .max_history_size(HISTORY_SIZE)
+            .map_err(|e| e.to_string())?
+            .auto_add_history(false)
+            .build();
+        let mut editor = Editor::with_config(config).map_err(|e| e.to_string())?;
+        editor.set_helper(Some(CalculatorHelper::default()));
+
+        let history_path = session::data_directory().map(|directory| directory.join("history"));
+        if let Some(path) = history_path.as_ref().filter(|path| path.exists()) {
+            if let Err(e) = editor.load_history(path) {
+                println!("Error: Unable to load history: {}", e);
+            }
+        }
+        Ok(LineEditor { editor, history_path })
+    }
+
+    // Reads a line of user input. Returns None at the end of input.
+    // Ctrl-C discards the current line.
+    pub fn read_line(&mut self, prompt: &str, session: &Session) -> Option<String> {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.read_session(session);
+        }
+
+        match self.editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = self.editor.add_history_entry(line.as_str());
+                    self.save_history();
+                }
+                Some(line)
+            },
+            Err(ReadlineError::Interrupted) => Some(String::new()),
+            Err(ReadlineError::Eof) => None,
+            Err(e) => {
+                println!("Error: {}", e);
+                None
+            },
+        }
+    }
+
+    // Writes the up/down history to the data directory.
+    fn save_history(&mut self) {
+        if let Some(path) = &self.history_path {
+            if let Some(directory) = path.parent() {
+                let _ = std::fs::create_dir_all(directory);
+            }
+            if let Err(e) = self.editor.save_history(path) {
+                println!("Error: Unable to save history: {}", e);
+            }
+        }
+    }
+}
+
+impl CalculatorHelper {
+    // Reads the names the session currently defines.
+    fn read_session(&mut self, session: &Session) {
+        self.variables = session.variables.keys().cloned().collect();
+        self.variables.sort();
+        self.functions = session.functions.keys().cloned().collect();
+        self.functions.sort();
+        self.aliases = session.config.aliases.keys().cloned().collect();
+        self.aliases.sort();
+    }
+}
+
+impl Completer for CalculatorHelper {
+    type Candidate = Pair;
+
+    // Completes the word before the cursor. Words starting with '/' complete to commands and aliases, and
+    // words after /set or /get complete to setting names.
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].char_indices().rev()
+            .take_while(|(_, c)| c.is_ascii_alphanumeric() || *c == '/')
+            .last()
+            .map_or(pos, |(index, _)| index);
+        let word = &line[start..pos];
+
+        let candidates: Vec<Pair> = if word.starts_with('/') {
+            COMMANDS.iter()
+                .copied()
+                .chain(self.aliases.iter().map(String::as_str))
+                .filter(|command| command.starts_with(word))
+                .map(|command| Pair { display: command.to_string(), replacement: command.to_string() })
+                .collect()
+        } else if matches!(line[..start].trim_end(), "/set" | "/get") {
+            settings::NAMES.iter()
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair { display: name.to_string(), replacement: name.to_string() })
+                .collect()
+        } else {
+            let functions = FUNCTIONS.iter()
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair { display: format!("{}[]", name), replacement: format!("{}[", name) });
+            let user_functions = self.functions.iter()
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair { display: format!("{}[]", name), replacement: format!("{}[", name) });
+            let variables = self.variables.iter()
+                .map(|variable| variable.to_string())
+                .filter(|variable| variable.starts_with(word))
+                .map(|variable| Pair { display: variable.clone(), replacement: variable });
+            functions.chain(user_functions).chain(variables).collect()
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for CalculatorHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CalculatorHelper {}
+
+impl Validator for CalculatorHelper {}
+
+impl Helper for CalculatorHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::parse_definition;
+    use rustyline::history::MemHistory;
+
+    fn complete(line: &str, session: &Session) -> Vec<String> {
+        let mut helper = CalculatorHelper::default();
+        helper.read_session(session);
+        let history = MemHistory::new();
+        let (_, candidates) = helper.complete(line, line.len(), &Context::new(&history)).unwrap();
+        candidates.into_iter().map(|pair| pair.replacement).collect()
+    }
+
+    #[test]
+    fn completes_user_functions_and_aliases() {
+        let mut session = Session::new();
+        let (name, function) = parse_definition("hyp(i, j) = iHj").unwrap();
+        session.functions.insert(name, function);
+        session.config.aliases.insert("/q".to_string(), "/x".to_string());
+
+        assert_eq!(complete("2 + hy", &session), ["hyp["]);
+        assert_eq!(complete("/q", &session), ["/q"]);
+        assert!(complete("/", &session).contains(&"/q".to_string()));
+        assert_eq!(complete("/sa", &session), ["/save"]);
+    }
+}
@@ -1,94 +1,90 @@
+use crate::datetime;
+use crate::matrix;
+use crate::probability;
+use crate::uncertainty;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+
+// Largest integer factorial and power results computed exactly.
+const MAX_FACTORIAL: u32 = 10000;
+const MAX_POWER_BITS: u64 = 1 << 20;
+
 // Evaluates a postfix expression.
-pub fn evaluate(expression: Vec<String>) -> Result<f64, String> {
-    let mut stack: Vec<f64> = Vec::new();
-    
+pub fn evaluate(expression: Vec<String>) -> Result<Value, String> {
+    let mut stack: Vec<Value> = Vec::new();
     for token in expression {
-        match token.as_str() {
-            "+" | "-" | "*" | "/" | "^" | "%" | "#" | "\\" | "R" | "L" | "H" => {
-                if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
-                    let result = match token.as_str() {
-                        "+" => a + b,
-                        "-" => a - b,
-                        "*" => a * b,
-                        "/" => {
-                            if b == 0.0 {
-                                let error_message = format!("Divide by Zero {} / {}", a, b);
-                                return Err(error_message);
-                            }
-                            a / b
-                        },
-                        "^" => a.powf(b),
-                        "%" => a % b,
-                        "#" => (a / b).trunc(),
-                        "\\" => (a / 100.0) * b,
-                        "R" => {
-                            if a == 0.0 {
-                                return Err("Cannot take 0th root of a number".to_string());
-                            }
-                            if b < 0.0 && a % 2.0 == 0.0 {
-                                return Err("Cannot take even root of a negative number".to_string());
-                            }
-                            b.powf(1.0 / a)
-                        },
-                        "L" => {
-                            if b < 0.0 {
-                                return Err("Cannot take log of a negative number".to_string());
-                            }
-                            if a < 0.0 {
-                                return Err("Cannot compute log with a negative base".to_string());
-                            }
-                            b.log10() / a.log10()
-                        },
-                        "H" => (a*a + b*b).sqrt(),
-                        operator => {
-                            let error_message = format!("Invalid Operator {}", operator);
-                            return Err(error_message)
-                        },
-                    };
-                    stack.push(result);
-                } else {
-                    return Err("Not enough operands".to_string());
-                }
-            },
-            "!" | "~" | "A" => {
-                if let Some(a) = stack.pop() {
-                    let result = match token.as_str() {
-                        "!" => {
-                            if a < 0.0 {
-                                return Err("Cannot take negative factorial".to_string());
-                            } 
-                            if a.fract() != 0.0 {
-                                return Err("Cannot evaluate decimal factorial 
-                                            (maybe in the future with gamma)".to_string());
-                            }
-                            let a_int = a as i32;
-                            let factorial = match a_int {
-                                0 | 1 => 1,
-                                _ => (2..=a_int).fold(1, |acc, x| acc * x),
-                            };
-                            factorial as f64
-                        },
-                        "~" => -a,
-                        "A" => a.abs(),
-                        operator => {
-                            let error_message = format!("Invalid Operator {}", operator);
-                            return Err(error_message);
-                        },
-                    };
-                    stack.push(result as f64);
-                }
-            },
-            operand => {
-                if let Ok(num) = operand.parse::<f64>() {
-                    stack.push(num);
-                } else {
-                    let error_message = format!("Invalid Operand {}", operand);
-                    return Err(error_message);
-                }
-            },
-        }
+        apply(&mut stack, &token)?;
+    }
+    result(stack)
+}
+
+// Applies an operator to the values on top of the stack, or pushes an operand.
+pub(crate) fn apply(stack: &mut Vec<Value>, token: &str) -> Result<(), String> {
+    match token {
+        "+" | "-" | "*" | "/" | "^" | "%" | "#" | "\\" | "R" | "L" | "H" | "±" | "±i" => {
+            if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
+                stack.push(binary_operation(token, a, b)?);
+            } else {
+                return Err("Not enough operands".to_string());
+            }
+        },
+        "<" | "<=" | "==" | "!=" | ">" | ">=" | "and" | "or" => {
+            if let (Some(b), Some(a)) = (stack.pop(), stack.pop()) {
+                let result = match token {
+                    "and" => a.is_true()? && b.is_true()?,
+                    "or" => a.is_true()? || b.is_true()?,
+                    operator => {
+                        let ordering = compare(&a, &b)?;
+                        match operator {
+                            "<" => ordering == Some(Ordering::Less),
+                            "<=" => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                            "==" => ordering == Some(Ordering::Equal),
+                            "!=" => ordering != Some(Ordering::Equal),
+                            ">" => ordering == Some(Ordering::Greater),
+                            _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                        }
+                    },
+                };
+                stack.push(Value::from_bool(result));
+            } else {
+                return Err("Not enough operands".to_string());
+            }
+        },
+        "!" | "~" | "A" | "not" => {
+            if let Some(a) = stack.pop() {
+                let result = match token {
+                    "!" => {
+                        let a = factorial_operand(a.to_f64()?)?;
+                        let factorial: BigInt = (2..=a).map(BigInt::from).product();
+                        Value::Integer(factorial)
+                    },
+                    "~" => negate(a)?,
+                    "A" => absolute(a)?,
+                    "not" => Value::from_bool(!a.is_true()?),
+                    operator => {
+                        let error_message = format!("Invalid Operator {}", operator);
+                        return Err(error_message);
+                    },
+                };
+                stack.push(result);
+            }
+        },
+        operand => {
+            if let Some(value) = probability::sample(operand).or_else(|| Value::parse(operand)) {
+                stack.push(value);
+            } else {
+                let error_message = format!("Invalid Operand {}", operand);
+                return Err(error_message);
+            }
+        },
     }
+    Ok(())
+}
 
+// Returns the value left by an evaluated expression, which must be the only one.
+pub(crate) fn result(mut stack: Vec<Value>) -> Result<Value, String> {
     if let Some(result) = stack.pop() {
         if stack.is_empty() {
             return Ok(result);
@@ -96,4 +92,192 @@ pub fn evaluate(expression: Vec<String>) -> Result<f64, String> {
         return Err("Too many operands".to_string());
     }
     Err("Empty expression".to_string())
-}
\ No newline at end of file
+}
+
+// Applies a binary operator. Integers are computed exactly when the result is an integer,
+// and operations on vectors, matrices, dates, durations, and uncertain values are left to their modules.
+pub(crate) fn binary_operation(operator: &str, a: Value, b: Value) -> Result<Value, String> {
+    if operator == "±" || operator == "±i" {
+        return uncertainty::create(operator, &a, &b);
+    }
+    if matches!(a, Value::List(_)) || matches!(b, Value::List(_)) {
+        return matrix::operation(operator, a, b);
+    }
+    if matches!(a, Value::Date(_) | Value::Duration(_)) || matches!(b, Value::Date(_) | Value::Duration(_)) {
+        return datetime::operation(operator, a, b);
+    }
+    if matches!(a, Value::Uncertain(_)) || matches!(b, Value::Uncertain(_)) {
+        return uncertainty::operation(operator, a, b);
+    }
+    if let (Value::Integer(a), Value::Integer(b)) = (&a, &b) {
+        if let Some(result) = integer_operation(operator, a, b)? {
+            return Ok(Value::Integer(result));
+        }
+    }
+
+    Ok(Value::Float(float_operation(operator, a.to_f64()?, b.to_f64()?)?))
+}
+
+// Applies a binary operator to two floats.
+pub(crate) fn float_operation(operator: &str, a: f64, b: f64) -> Result<f64, String> {
+    let result = match operator {
+        "+" => a + b,
+        "-" => a - b,
+        "*" => a * b,
+        "/" => {
+            if b == 0.0 {
+                let error_message = format!("Divide by Zero {} / {}", a, b);
+                return Err(error_message);
+            }
+            a / b
+        },
+        "^" => a.powf(b),
+        "%" => a % b,
+        "#" => (a / b).trunc(),
+        "\\" => (a / 100.0) * b,
+        "R" => {
+            if a == 0.0 {
+                return Err("Cannot take 0th root of a number".to_string());
+            }
+            if b < 0.0 && a % 2.0 == 0.0 {
+                return Err("Cannot take even root of a negative number".to_string());
+            }
+            b.powf(1.0 / a)
+        },
+        "L" => {
+            if b < 0.0 {
+                return Err("Cannot take log of a negative number".to_string());
+            }
+            if a < 0.0 {
+                return Err("Cannot compute log with a negative base".to_string());
+            }
+            b.log10() / a.log10()
+        },
+        "H" => (a*a + b*b).sqrt(),
+        operator => {
+            let error_message = format!("Invalid Operator {}", operator);
+            return Err(error_message)
+        },
+    };
+    Ok(result)
+}
+
+// Checks that a factorial can be taken of a number, returning it as a whole number.
+pub(crate) fn factorial_operand(a: f64) -> Result<u32, String> {
+    if a < 0.0 {
+        return Err("Cannot take negative factorial".to_string());
+    }
+    if a.fract() != 0.0 {
+        return Err("Cannot evaluate decimal factorial
+                    (maybe in the future with gamma)".to_string());
+    }
+    if a > MAX_FACTORIAL as f64 {
+        let error_message = format!("Cannot take factorial larger than {}", MAX_FACTORIAL);
+        return Err(error_message);
+    }
+    Ok(a as u32)
+}
+
+// Negates a number, duration, or uncertain value, or every number of a vector or matrix.
+fn negate(a: Value) -> Result<Value, String> {
+    match a {
+        Value::Integer(a) => Ok(Value::Integer(-a)),
+        Value::List(_) => matrix::map(a, &negate),
+        Value::Duration(duration) => Ok(Value::Duration(-duration)),
+        Value::Uncertain(uncertain) => Ok(Value::Uncertain(uncertain.negate())),
+        a => Ok(Value::Float(-a.to_f64()?)),
+    }
+}
+
+// Takes the absolute value of a number, duration, or uncertain value, or of every number of a vector or matrix.
+fn absolute(a: Value) -> Result<Value, String> {
+    match a {
+        Value::Integer(a) => Ok(Value::Integer(a.abs())),
+        Value::List(_) => matrix::map(a, &absolute),
+        Value::Duration(duration) => Ok(Value::Duration(duration.abs())),
+        Value::Uncertain(uncertain) => Ok(Value::Uncertain(uncertain.abs())),
+        a => Ok(Value::Float(a.to_f64()?.abs())),
+    }
+}
+
+// Compares two numbers, exactly when both are integers, or two dates or durations. NaN is unordered.
+fn compare(a: &Value, b: &Value) -> Result<Option<Ordering>, String> {
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => Ok(Some(a.cmp(b))),
+        (Value::Date(_) | Value::Duration(_), _) | (_, Value::Date(_) | Value::Duration(_)) => {
+            datetime::compare(a, b).map(Some)
+        },
+        (a, b) => Ok(a.to_f64()?.partial_cmp(&b.to_f64()?)),
+    }
+}
+
+// Computes a binary operation exactly on integers.
+// Returns None when the result is not an integer and must be computed as a float.
+fn integer_operation(operator: &str, a: &BigInt, b: &BigInt) -> Result<Option<BigInt>, String> {
+    if b.is_zero() && matches!(operator, "/" | "%" | "#") {
+        let error_message = format!("Divide by Zero {} {} {}", a, operator, b);
+        return Err(error_message);
+    }
+
+    let result = match operator {
+        "+" => Some(a + b),
+        "-" => Some(a - b),
+        "*" => Some(a * b),
+        "/" if (a % b).is_zero() => Some(a / b),
+        "%" => Some(a % b),
+        "#" => Some(a / b),
+        "^" => {
+            match b.to_u32() {
+                Some(exponent) if a.is_zero() || a.abs().is_one()
+                    || a.bits() * exponent as u64 <= MAX_POWER_BITS => Some(a.pow(exponent)),
+                _ => None,
+            }
+        },
+        _ => None,
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn postfix(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|token| token.to_string()).collect()
+    }
+
+    #[test]
+    fn integer_operations_are_exact() {
+        let result = evaluate(postfix(&["2", "100", "^", "1", "+"])).unwrap();
+        assert_eq!(result.to_string(), "1267650600228229401496703205377");
+        assert_eq!(evaluate(postfix(&["7", "2", "/"])), Ok(Value::Float(3.5)));
+        assert_eq!(evaluate(postfix(&["8", "2", "/"])), Ok(Value::Integer(BigInt::from(4))));
+    }
+
+    #[test]
+    fn integer_division_by_zero_is_an_error() {
+        assert!(evaluate(postfix(&["5", "0", "%"])).is_err());
+        assert!(evaluate(postfix(&["5", "0", "#"])).is_err());
+        assert!(evaluate(postfix(&["5", "0", "/"])).is_err());
+    }
+
+    #[test]
+    fn comparisons_and_booleans_return_one_or_zero() {
+        let one = Ok(Value::Integer(BigInt::one()));
+        let zero = Ok(Value::Integer(BigInt::zero()));
+        assert_eq!(evaluate(postfix(&["99999999999999999999", "99999999999999999998", ">"])), one);
+        assert_eq!(evaluate(postfix(&["0.5", "1", "<="])), one);
+        assert_eq!(evaluate(postfix(&["2", "2.0", "!="])), zero);
+        assert_eq!(evaluate(postfix(&["NaN", "NaN", "=="])), zero);
+        assert_eq!(evaluate(postfix(&["2", "0", "and", "not"])), one);
+        assert_eq!(evaluate(postfix(&["0", "0.1", "or"])), one);
+        assert!(evaluate(postfix(&["[1]", "1", "=="])).is_err());
+    }
+
+    #[test]
+    fn factorial_is_limited() {
+        assert_eq!(evaluate(postfix(&["0", "!"])), Ok(Value::Integer(BigInt::from(1))));
+        assert!(evaluate(postfix(&["100000", "!"])).is_err());
+        assert!(evaluate(postfix(&["2.5", "!"])).is_err());
+    }
+}
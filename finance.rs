@@ -0,0 +1,298 @@
+use crate::complex_evaluate::separate_vector;
+use crate::evaluate::evaluate;
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::value::Value;
+
+// Largest number of Newton steps taken when solving for a rate.
+const MAX_ITERATIONS: usize = 100;
+// Rates are solved to this accuracy.
+const RATE_TOLERANCE: f64 = 1E-12;
+// Longest amortization schedule.
+const MAX_PERIODS: f64 = 10_000.0;
+
+// Evaluates comma separated financial values. Values may be lists.
+fn values(tokens: &Vec<String>) -> Result<Vec<Value>, &'static str> {
+    separate_vector(tokens, 0).iter()
+        .map(|equation_tokens| evaluate(infix_to_postfix(equation_tokens)).map_err(|_| "Could not evaluate financial value"))
+        .collect()
+}
+
+// Converts a value to a float, failing for anything but numbers.
+fn number(value: &Value) -> Result<f64, &'static str> {
+    value.to_f64().map_err(|_| "Financial values must be numbers or lists of numbers")
+}
+
+// Computes a result from numbers. When arguments are lists, it is computed for each of their elements
+// and a list is returned. Numbers are used for every element, so 'pmt[[0.04, 0.05]/12, 360, 200000]'
+// compares two rates.
+fn each(arguments: &[Value], compute: impl Fn(&[f64]) -> Result<f64, &'static str>) -> Result<String, &'static str> {
+    let finite = |result: f64| if result.is_finite() { Ok(result) } else { Err("No finite result for these values") };
+    let length = arguments.iter().find_map(|argument| match argument {
+        Value::List(items) => Some(items.len()),
+        _ => None,
+    });
+    let Some(length) = length else {
+        let numbers = arguments.iter().map(number).collect::<Result<Vec<f64>, &str>>()?;
+        return Ok(finite(compute(&numbers)?)?.to_string());
+    };
+
+    let mut results = Vec::new();
+    for index in 0..length {
+        let numbers = arguments.iter().map(|argument| match argument {
+            Value::List(items) if items.len() == length => number(&items[index]),
+            Value::List(_) => Err("Financial list arguments must have the same length"),
+            argument => number(argument),
+        }).collect::<Result<Vec<f64>, &str>>()?;
+        results.push(Value::Float(finite(compute(&numbers)?)?));
+    }
+    Ok(Value::List(results).to_string())
+}
+
+// Evaluates the arguments of a time value of money function, adding a future value or present value of 0
+// and payments at the end of each period when they are left out.
+fn time_value_arguments(tokens: &Vec<String>, usage: &'static str) -> Result<Vec<Value>, &'static str> {
+    let mut arguments = values(tokens)?;
+    if !(3..=5).contains(&arguments.len()) {
+        return Err(usage);
+    }
+    arguments.resize(5, Value::Float(0.0));
+    Ok(arguments)
+}
+
+// Time value of money functions follow the usual spreadsheet sign convention: money received is
+// positive and money paid is negative, so a loan of 1000 has a negative payment.
+// 'due' is 1 for payments at the start of each period and 0 for payments at the end.
+
+// Future value of a present value and payments.
+fn future_value(rate: f64, periods: f64, payment: f64, present: f64, due: f64) -> f64 {
+    if rate == 0.0 {
+        return -(present + payment * periods);
+    }
+    let growth = (1.0 + rate).powf(periods);
+    -(present * growth + payment * (1.0 + rate * due) * (growth - 1.0) / rate)
+}
+
+// Computes the present value of payments and a future value as [rate, periods, payment, future, due].
+pub fn pv(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = time_value_arguments(tokens, "Expected pv[rate, periods, payment, future value, due]")?;
+    each(&arguments, |numbers| {
+        let [rate, periods, payment, future, due] = numbers else { unreachable!() };
+        if *rate == 0.0 {
+            return Ok(-(future + payment * periods));
+        }
+        let growth = (1.0 + rate).powf(*periods);
+        Ok(-(future + payment * (1.0 + rate * due) * (growth - 1.0) / rate) / growth)
+    })
+}
+
+// Computes the future value of a present value and payments as [rate, periods, payment, present, due].
+pub fn fv(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = time_value_arguments(tokens, "Expected fv[rate, periods, payment, present value, due]")?;
+    each(&arguments, |numbers| {
+        let [rate, periods, payment, present, due] = *numbers else { unreachable!() };
+        Ok(future_value(rate, periods, payment, present, due))
+    })
+}
+
+// Computes the payment each period as [rate, periods, present, future, due].
+pub fn pmt(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = time_value_arguments(tokens, "Expected pmt[rate, periods, present value, future value, due]")?;
+    each(&arguments, |numbers| Ok(payment(numbers[0], numbers[1], numbers[2], numbers[3], numbers[4])))
+}
+
+fn payment(rate: f64, periods: f64, present: f64, future: f64, due: f64) -> f64 {
+    if rate == 0.0 {
+        return -(present + future) / periods;
+    }
+    let growth = (1.0 + rate).powf(periods);
+    -(future + present * growth) * rate / ((1.0 + rate * due) * (growth - 1.0))
+}
+
+// Computes the number of periods as [rate, payment, present, future, due].
+pub fn nper(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = time_value_arguments(tokens, "Expected nper[rate, payment, present value, future value, due]")?;
+    each(&arguments, |numbers| {
+        let [rate, payment, present, future, due] = *numbers else { unreachable!() };
+        if rate == 0.0 {
+            return Ok(-(present + future) / payment);
+        }
+        let annuity = payment * (1.0 + rate * due) / rate;
+        Ok(((annuity - future) / (annuity + present)).ln() / (1.0 + rate).ln())
+    })
+}
+
+// Computes the rate per period as [periods, payment, present, future, due], solved with Newton's method.
+pub fn rate(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = time_value_arguments(tokens, "Expected rate[periods, payment, present value, future value, due]")?;
+    each(&arguments, |numbers| {
+        let [periods, payment, present, future, due] = *numbers else { unreachable!() };
+        solve_rate(|rate| future_value(rate, periods, payment, present, due) - future)
+            .ok_or("rate did not converge for these values")
+    })
+}
+
+// Computes the net present value of cash flows as [rate, flow, flow, ...]. Cash flows may be given as a list.
+// The first cash flow is at the start, so it is not discounted.
+pub fn npv(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = values(tokens)?;
+    let (rate, flows) = match arguments.split_first() {
+        Some((rate, flows)) if !flows.is_empty() => (number(rate)?, cash_flows(flows)?),
+        _ => return Err("Expected npv[rate, cash flows]"),
+    };
+    Ok(net_present_value(rate, &flows).to_string())
+}
+
+// Computes the internal rate of return of cash flows as [flow, flow, ...], the rate with a net present value
+// of 0. Cash flows may be given as a list.
+pub fn irr(tokens: &Vec<String>) -> Result<String, &str> {
+    let flows = cash_flows(&values(tokens)?)?;
+    if !flows.iter().any(|flow| *flow > 0.0) || !flows.iter().any(|flow| *flow < 0.0) {
+        return Err("irr needs at least one positive and one negative cash flow");
+    }
+    let result = solve_rate(|rate| net_present_value(rate, &flows))
+        .or_else(|| bisect_rate(|rate| net_present_value(rate, &flows)))
+        .ok_or("irr did not converge for these cash flows")?;
+    Ok(result.to_string())
+}
+
+// Computes a loan's amortization schedule as [rate, periods, present]. Each row of the resulting matrix is
+// [period, payment, interest, principal, balance], with the payment made at the end of the period.
+pub fn amortization(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = values(tokens)?;
+    let [rate, periods, present] = arguments.iter().map(number).collect::<Result<Vec<f64>, &str>>()?[..] else {
+        return Err("Expected amort[rate, periods, present value]");
+    };
+    if periods.fract() != 0.0 || !(1.0..=MAX_PERIODS).contains(&periods) {
+        return Err("amort periods must be a whole number from 1 to 10000");
+    }
+
+    let installment = -payment(rate, periods, present, 0.0, 0.0);
+    let mut balance = present;
+    let mut rows = Vec::new();
+    for period in 1..=periods as u32 {
+        let interest = balance * rate;
+        let principal = installment - interest;
+        balance -= principal;
+        // Clear rounding errors left in the final balance.
+        if period == periods as u32 || balance.abs() < RATE_TOLERANCE * present.abs() {
+            balance = 0.0;
+        }
+        let row = [period as f64, installment, interest, principal, balance];
+        rows.push(Value::List(row.into_iter().map(Value::Float).collect()));
+    }
+    if !installment.is_finite() {
+        return Err("No finite result for these values");
+    }
+    Ok(Value::List(rows).to_string())
+}
+
+// Reads cash flows given as numbers or lists of numbers.
+fn cash_flows(arguments: &[Value]) -> Result<Vec<f64>, &'static str> {
+    let mut flows = Vec::new();
+    for argument in arguments {
+        match argument {
+            Value::List(items) => flows.extend(items.iter().map(number).collect::<Result<Vec<f64>, &str>>()?),
+            argument => flows.push(number(argument)?),
+        }
+    }
+    Ok(flows)
+}
+
+fn net_present_value(rate: f64, flows: &[f64]) -> f64 {
+    flows.iter().rev().fold(0.0, |total, flow| total / (1.0 + rate) + flow)
+}
+
+// Finds a root of f near 10% with Newton's method, using a numerical derivative.
+fn solve_rate(f: impl Fn(f64) -> f64) -> Option<f64> {
+    let mut rate = 0.1;
+    for _ in 0..MAX_ITERATIONS {
+        let value = f(rate);
+        let step = 1E-7 * rate.abs().max(1E-3);
+        let slope = (f(rate + step) - f(rate - step)) / (2.0 * step);
+        if !value.is_finite() || slope == 0.0 || !slope.is_finite() {
+            return None;
+        }
+        let next = rate - value / slope;
+        if next <= -1.0 {
+            return None;
+        }
+        if (next - rate).abs() < RATE_TOLERANCE * next.abs().max(1.0) {
+            return Some(next);
+        }
+        rate = next;
+    }
+    None
+}
+
+// Finds a root of f between -99% and 1000% by bisection, if f changes sign there.
+fn bisect_rate(f: impl Fn(f64) -> f64) -> Option<f64> {
+    let (mut low, mut high) = (-0.99, 10.0);
+    if f(low).signum() == f(high).signum() {
+        return None;
+    }
+    for _ in 0..200 {
+        let middle = (low + high) / 2.0;
+        if f(middle).signum() == f(low).signum() {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Separates argument tokens with commas, as tokenize does.
+    fn arguments(values: &[&str]) -> Vec<String> {
+        values.iter().flat_map(|value| [",".to_string(), value.to_string()]).skip(1).collect()
+    }
+
+    fn call(function: fn(&Vec<String>) -> Result<String, &str>, values: &[&str]) -> f64 {
+        function(&arguments(values)).unwrap().parse().unwrap()
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1E-6 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn time_value_functions_agree() {
+        // A 30 year loan of 200000 at 6% a year, paid monthly.
+        let payment = call(pmt, &["0.005", "360", "200000"]);
+        assert!(close(payment, -1199.101050));
+        let payment = payment.to_string();
+        assert!(close(call(pv, &["0.005", "360", &payment]), 200000.0));
+        assert!(close(call(nper, &["0.005", &payment, "200000"]), 360.0));
+        assert!(close(call(rate, &["360", &payment, "200000"]), 0.005));
+        assert!(close(call(fv, &["0.05", "10", "0", "-1000"]), 1628.894627));
+        assert!(close(call(fv, &["0", "10", "-100"]), 1000.0));
+        assert!(close(call(pmt, &["0.01", "12", "1000", "0", "1"]), -87.969098));
+        assert!(pmt(&arguments(&["0.01", "12"])).is_err());
+    }
+
+    #[test]
+    fn cash_flows_are_discounted() {
+        assert!(close(call(irr, &["-1000", "300", "400", "500"]), 0.0889633947));
+        assert!(close(call(irr, &["[-1000, 300, 400, 500]"]), 0.0889633947));
+        assert!(close(call(npv, &["0.1", "[-1000, 300, 400, 500]"]), -21.036814));
+        assert!(irr(&arguments(&["100", "200"])).is_err());
+    }
+
+    #[test]
+    fn builds_amortization_schedules() {
+        let schedule = Value::parse(&amortization(&arguments(&["0.01", "2", "1000"])).unwrap()).unwrap();
+        let Value::List(rows) = schedule else { panic!("expected a schedule") };
+        assert_eq!(rows.len(), 2);
+        assert!(rows[1].to_string().ends_with(", 0]"));
+        assert!(amortization(&arguments(&["0.01", "2.5", "1000"])).is_err());
+    }
+
+    #[test]
+    fn list_arguments_give_lists() {
+        assert_eq!(fv(&arguments(&["[0, 0.5]", "1", "0", "-100"])), Ok("[100, 150]".to_string()));
+        assert!(fv(&arguments(&["[0, 0.1]", "[1, 2, 3]", "-100"])).is_err());
+    }
+}
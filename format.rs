@@ -0,0 +1,521 @@
+use crate::datetime;
+use crate::uncertainty::Uncertain;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_traits::Signed;
+use std::fmt;
+
+// Largest number of decimals or significant digits shown.
+pub const MAX_DIGITS: usize = 100;
+
+// Decimal exponents outside this range are shown in scientific notation by sig.
+const POSITIONAL_EXPONENTS: std::ops::RangeInclusive<i64> = -5..=21;
+
+// How numbers are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Notation {
+    Auto,
+    Fixed,
+    Significant,
+    Scientific,
+    Engineering,
+}
+
+// How digits beyond the displayed precision are rounded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    HalfUp,
+    HalfEven,
+    HalfDown,
+    Up,
+    Down,
+    Floor,
+    Ceiling,
+}
+
+// Decimal, digit group, and argument separators of numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Locale {
+    English,
+    German,
+    French,
+}
+
+// Display format of results and variables. Values are always stored at full precision.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    pub notation: Notation,
+    pub digits: usize,
+    pub grouping: bool,
+    pub rounding: Rounding,
+    pub locale: Locale,
+}
+
+// A finite decimal number 0.digits * 10^exponent, without leading or trailing zero digits.
+// Zero has no digits.
+#[derive(Clone, Debug, PartialEq)]
+struct Decimal {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+impl Notation {
+    // Parses a notation name entered with /format.
+    pub fn parse(name: &str) -> Option<Notation> {
+        match name {
+            "auto" => Some(Notation::Auto),
+            "fixed" => Some(Notation::Fixed),
+            "sig" => Some(Notation::Significant),
+            "sci" => Some(Notation::Scientific),
+            "eng" => Some(Notation::Engineering),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Notation::Auto => "auto",
+            Notation::Fixed => "fixed",
+            Notation::Significant => "sig",
+            Notation::Scientific => "sci",
+            Notation::Engineering => "eng",
+        }
+    }
+}
+
+impl Rounding {
+    // Parses a rounding mode entered with /format.
+    pub fn parse(name: &str) -> Option<Rounding> {
+        match name {
+            "half-up" => Some(Rounding::HalfUp),
+            "half-even" => Some(Rounding::HalfEven),
+            "half-down" => Some(Rounding::HalfDown),
+            "up" => Some(Rounding::Up),
+            "down" => Some(Rounding::Down),
+            "floor" => Some(Rounding::Floor),
+            "ceiling" => Some(Rounding::Ceiling),
+            _ => None,
+        }
+    }
+}
+
+impl Locale {
+    // Parses a locale name entered with /format.
+    pub fn parse(name: &str) -> Option<Locale> {
+        match name {
+            "en" => Some(Locale::English),
+            "de" => Some(Locale::German),
+            "fr" => Some(Locale::French),
+            _ => None,
+        }
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::English => '.',
+            Locale::German | Locale::French => ',',
+        }
+    }
+
+    pub fn group_separator(&self) -> char {
+        match self {
+            Locale::English => ',',
+            Locale::German => '.',
+            Locale::French => ' ',
+        }
+    }
+
+    // Separates complex operator values. Commas are decimals when they are not arguments.
+    pub fn argument_separator(&self) -> char {
+        match self {
+            Locale::English => ',',
+            Locale::German | Locale::French => ';',
+        }
+    }
+
+    // Whether group separators may be entered within numbers, as in 1.234,5.
+    // Spaces and argument separators cannot be, since they already separate values.
+    pub fn groups_input(&self) -> bool {
+        *self == Locale::German
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Locale::English => "en",
+            Locale::German => "de",
+            Locale::French => "fr",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Rounding::HalfUp => "half-up",
+            Rounding::HalfEven => "half-even",
+            Rounding::HalfDown => "half-down",
+            Rounding::Up => "up",
+            Rounding::Down => "down",
+            Rounding::Floor => "floor",
+            Rounding::Ceiling => "ceiling",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, grouping {}, rounding {}, locale {}", self.notation_name(),
+               if self.grouping { "on" } else { "off" }, self.rounding, self.locale)
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat {
+            notation: Notation::Auto,
+            digits: 6,
+            grouping: false,
+            rounding: Rounding::HalfUp,
+            locale: Locale::English,
+        }
+    }
+}
+
+impl NumberFormat {
+    // Describes the notation and digits the way /format reads them, like 'fixed 4'.
+    pub fn notation_name(&self) -> String {
+        match self.notation {
+            Notation::Auto => self.notation.to_string(),
+            notation => format!("{} {}", notation, self.digits),
+        }
+    }
+
+    // Parses a notation and digits written by notation_name.
+    pub fn set_notation(&mut self, text: &str) -> Result<(), String> {
+        let mut words = text.split_whitespace();
+        let notation = words.next().and_then(Notation::parse)
+            .ok_or_else(|| format!("{} is not a valid notation", text))?;
+        let digits = match (notation, words.next()) {
+            (Notation::Auto, None) => self.digits,
+            (Notation::Auto, Some(_)) => return Err("auto does not take digits".to_string()),
+            (_, Some(digits)) => digits.parse::<usize>().ok()
+                .filter(|digits| *digits <= MAX_DIGITS && (*digits > 0 || notation == Notation::Fixed))
+                .ok_or_else(|| format!("{} is not a valid number of digits", digits))?,
+            (_, None) => return Err(format!("Missing digits. Try /format {} 4", notation)),
+        };
+        if words.next().is_some() {
+            return Err(format!("Unexpected text after {}", text));
+        }
+        self.notation = notation;
+        self.digits = digits;
+        Ok(())
+    }
+
+    // Formats a value for display.
+    pub fn format(&self, value: &Value) -> String {
+        let decimal = match value {
+            Value::Integer(integer) if self.notation == Notation::Auto => {
+                return self.localize(&integer.to_string());
+            },
+            Value::Integer(integer) => Decimal::from_integer(integer),
+            Value::Float(float) if !float.is_finite() => return float.to_string(),
+            Value::Float(float) if self.notation == Notation::Auto => return self.localize(&float.to_string()),
+            Value::Float(float) => Decimal::from_float(*float),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| self.format(item)).collect();
+                return format!("[{}]", items.join(&format!("{} ", self.locale.argument_separator())));
+            },
+            Value::Date(date) => return datetime::format_date(date),
+            Value::Duration(duration) => return datetime::format_duration(*duration),
+            Value::Uncertain(Uncertain::Gaussian { value, error }) => {
+                return self.uncertain([*value, *error], *error, " ± ");
+            },
+            Value::Uncertain(Uncertain::Interval { low, high }) => {
+                return self.uncertain([*low, *high], (high - low) / 2.0, " to ");
+            },
+        };
+
+        let digits = self.digits as i64;
+        match self.notation {
+            Notation::Fixed => {
+                let rounded = decimal.round(decimal.exponent + digits, self.rounding);
+                self.positional(&rounded, self.digits)
+            },
+            Notation::Significant => {
+                let rounded = decimal.round(digits, self.rounding);
+                if rounded.digits.is_empty() {
+                    self.positional(&rounded, self.digits - 1)
+                } else if POSITIONAL_EXPONENTS.contains(&rounded.exponent) {
+                    self.positional(&rounded, (digits - rounded.exponent).max(0) as usize)
+                } else {
+                    self.scientific(&rounded, 1)
+                }
+            },
+            Notation::Scientific => self.scientific(&decimal.round(digits, self.rounding), 1),
+            Notation::Engineering => {
+                let rounded = decimal.round(digits, self.rounding);
+                let integer_digits = if rounded.digits.is_empty() {
+                    1
+                } else {
+                    (rounded.exponent - 1).rem_euclid(3) + 1
+                };
+                self.scientific(&rounded, integer_digits)
+            },
+            Notation::Auto => unreachable!(),
+        }
+    }
+
+    // Formats a stored value, leaving text that is not a value unchanged.
+    pub fn format_text(&self, text: &str) -> String {
+        match Value::parse(text) {
+            Some(value) => self.format(&value),
+            None => text.to_string(),
+        }
+    }
+
+    // Writes the two numbers of an uncertain value. In auto notation both are rounded to the second
+    // significant digit of the uncertainty, leaving out a trailing zero, as in '9.81 ± 0.02'.
+    fn uncertain(&self, numbers: [f64; 2], uncertainty: f64, separator: &str) -> String {
+        let rounded = self.notation == Notation::Auto && uncertainty.is_normal()
+            && numbers.iter().all(|number| number.is_finite());
+        let [a, b] = if rounded {
+            let fixed = |number: f64, decimals: usize| {
+                let decimal = Decimal::from_float(number);
+                self.positional(&decimal.round(decimal.exponent + decimals as i64, self.rounding), decimals)
+            };
+            let mut decimals = (1 - uncertainty.abs().log10().floor() as i64).clamp(0, MAX_DIGITS as i64) as usize;
+            if decimals > 0 && fixed(uncertainty, decimals).ends_with('0') {
+                decimals -= 1;
+            }
+            numbers.map(|number| fixed(number, decimals))
+        } else {
+            numbers.map(|number| self.format(&Value::Float(number)))
+        };
+        format!("{}{}{}", a, separator, b)
+    }
+
+    // Writes a decimal with a fixed number of decimals.
+    fn positional(&self, decimal: &Decimal, decimals: usize) -> String {
+        let integer_digits = decimal.exponent.max(0) as usize;
+        let mut integer: String = (0..integer_digits).map(|index| decimal.digit(index as i64)).collect();
+        if integer.is_empty() {
+            integer.push('0');
+        }
+        let fraction: String = (0..decimals).map(|index| decimal.digit(decimal.exponent + index as i64)).collect();
+
+        let mut text = String::new();
+        if decimal.negative && !decimal.digits.is_empty() {
+            text.push('-');
+        }
+        text.push_str(&integer);
+        if decimals > 0 {
+            text.push('.');
+            text.push_str(&fraction);
+        }
+        self.localize(&text)
+    }
+
+    // Writes a decimal as a mantissa with integer_digits digits before the point and an exponent.
+    fn scientific(&self, decimal: &Decimal, integer_digits: i64) -> String {
+        let exponent = if decimal.digits.is_empty() { 0 } else { decimal.exponent - integer_digits };
+        let shown_digits = (self.digits as i64).max(integer_digits);
+        let mantissa = Decimal { exponent: integer_digits, ..decimal.clone() };
+        format!("{}E{}", self.positional(&mantissa, (shown_digits - integer_digits) as usize), exponent)
+    }
+
+    // Replaces the '.' of a number with the locale's decimal separator, and inserts group separators
+    // into its integer part when grouping is on.
+    fn localize(&self, number: &str) -> String {
+        let (sign, unsigned) = number.strip_prefix('-').map_or(("", number), |unsigned| ("-", unsigned));
+        let end = unsigned.find(|c: char| !c.is_ascii_digit()).unwrap_or(unsigned.len());
+        let (integer, rest) = unsigned.split_at(end);
+
+        let mut grouped = String::new();
+        for (index, c) in integer.chars().enumerate() {
+            if self.grouping && index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push(self.locale.group_separator());
+            }
+            grouped.push(c);
+        }
+        let rest = rest.replacen('.', &self.locale.decimal_separator().to_string(), 1);
+        format!("{}{}{}", sign, grouped, rest)
+    }
+}
+
+impl Decimal {
+    fn from_integer(integer: &BigInt) -> Decimal {
+        let text = integer.abs().to_string();
+        Decimal::new(integer.is_negative(), &text, text.len() as i64)
+    }
+
+    // Uses the shortest digits that read back as the same float, so 2.675 rounds like 2.675.
+    fn from_float(float: f64) -> Decimal {
+        let text = format!("{:e}", float.abs());
+        let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+        let exponent = exponent.parse::<i64>().unwrap_or(0) + 1;
+        Decimal::new(float.is_sign_negative(), &mantissa.replace('.', ""), exponent)
+    }
+
+    fn new(negative: bool, digits: &str, exponent: i64) -> Decimal {
+        let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+        let digits: Vec<u8> = digits.trim_start_matches('0').trim_end_matches('0').bytes()
+            .map(|digit| digit - b'0')
+            .collect();
+        let exponent = if digits.is_empty() { 0 } else { exponent - leading_zeros as i64 };
+        Decimal { negative, digits, exponent }
+    }
+
+    // Returns the digit at a position of 0.digits, or '0' outside of the digits.
+    fn digit(&self, position: i64) -> char {
+        match usize::try_from(position) {
+            Ok(index) if index < self.digits.len() => (b'0' + self.digits[index]) as char,
+            _ => '0',
+        }
+    }
+
+    // Rounds to the first keep digits, which may be 0 or negative for numbers below the precision.
+    fn round(&self, keep: i64, rounding: Rounding) -> Decimal {
+        if keep >= self.digits.len() as i64 {
+            return self.clone();
+        }
+        let kept = &self.digits[..keep.max(0) as usize];
+        let dropped = &self.digits[keep.max(0) as usize..];
+        let first_dropped = if keep < 0 { 0 } else { dropped[0] };
+        let rest_nonzero = dropped.iter().skip(if keep < 0 { 0 } else { 1 }).any(|digit| *digit != 0);
+        let last_odd = kept.last().is_some_and(|digit| digit % 2 == 1);
+
+        let increment = match rounding {
+            Rounding::Down => false,
+            Rounding::Up => true,
+            Rounding::Floor => self.negative,
+            Rounding::Ceiling => !self.negative,
+            Rounding::HalfUp => first_dropped >= 5,
+            Rounding::HalfDown => first_dropped > 5 || (first_dropped == 5 && rest_nonzero),
+            Rounding::HalfEven => first_dropped > 5 || (first_dropped == 5 && (rest_nonzero || last_odd)),
+        };
+
+        let text: String = kept.iter().map(|digit| (b'0' + digit) as char).collect();
+        if !increment {
+            return Decimal::new(self.negative, &text, self.exponent);
+        }
+        // Adding one unit in the last kept place may carry into a new leading digit.
+        if keep < 0 {
+            return Decimal::new(self.negative, "1", self.exponent - keep + 1);
+        }
+        let mut digits: Vec<u8> = kept.to_vec();
+        let mut exponent = self.exponent;
+        let mut position = digits.len();
+        loop {
+            if position == 0 {
+                digits.insert(0, 1);
+                exponent += 1;
+                break;
+            }
+            position -= 1;
+            if digits[position] == 9 {
+                digits[position] = 0;
+            } else {
+                digits[position] += 1;
+                break;
+            }
+        }
+        let text: String = digits.iter().map(|digit| (b'0' + digit) as char).collect();
+        Decimal::new(self.negative, &text, exponent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(notation: &str, rounding: Rounding, value: Value) -> String {
+        let mut number_format = NumberFormat { rounding, ..NumberFormat::default() };
+        number_format.set_notation(notation).unwrap();
+        number_format.format(&value)
+    }
+
+    #[test]
+    fn formats_fixed_decimals() {
+        assert_eq!(format("fixed 2", Rounding::HalfUp, Value::Float(3.9999999999999982)), "4.00");
+        assert_eq!(format("fixed 2", Rounding::HalfUp, Value::Float(2.675)), "2.68");
+        assert_eq!(format("fixed 0", Rounding::HalfEven, Value::Float(2.5)), "2");
+        assert_eq!(format("fixed 3", Rounding::HalfUp, Value::Float(-0.0004)), "0.000");
+        assert_eq!(format("fixed 1", Rounding::Up, Value::Float(0.001)), "0.1");
+        assert_eq!(format("fixed 1", Rounding::Floor, Value::Float(-0.001)), "-0.1");
+        assert_eq!(format("fixed 2", Rounding::HalfUp, Value::Integer(BigInt::from(-42))), "-42.00");
+    }
+
+    #[test]
+    fn formats_significant_digits() {
+        assert_eq!(format("sig 4", Rounding::HalfUp, Value::Float(13333333335.133333)), "13330000000");
+        assert_eq!(format("sig 2", Rounding::HalfUp, Value::Float(0.001234)), "0.0012");
+        assert_eq!(format("sig 3", Rounding::HalfUp, Value::Float(9.999)), "10.0");
+        assert_eq!(format("sig 3", Rounding::HalfUp, Value::Float(1.5E30)), "1.50E30");
+        assert_eq!(format("sig 3", Rounding::HalfUp, Value::Integer(BigInt::from(0))), "0.00");
+    }
+
+    #[test]
+    fn formats_scientific_and_engineering_notation() {
+        assert_eq!(format("sci 3", Rounding::HalfUp, Value::Float(13333333335.133333)), "1.33E10");
+        assert_eq!(format("sci 3", Rounding::HalfUp, Value::Float(-0.000123456)), "-1.23E-4");
+        assert_eq!(format("sci 2", Rounding::HalfUp, Value::Float(9.96)), "1.0E1");
+        assert_eq!(format("eng 4", Rounding::HalfUp, Value::Float(13333333335.133333)), "13.33E9");
+        assert_eq!(format("eng 3", Rounding::HalfUp, Value::Float(0.000123456)), "123E-6");
+        assert_eq!(format("eng 3", Rounding::HalfUp, Value::Float(0.0)), "0.00E0");
+    }
+
+    #[test]
+    fn groups_thousands() {
+        let number_format = NumberFormat { grouping: true, ..NumberFormat::default() };
+        assert_eq!(number_format.format(&Value::Integer(BigInt::from(-1234567))), "-1,234,567");
+        assert_eq!(number_format.format(&Value::Float(1234.5)), "1,234.5");
+        assert_eq!(number_format.format(&Value::Float(123.0)), "123");
+        let list = Value::List(vec![Value::Integer(BigInt::from(1000)), Value::Float(f64::NAN)]);
+        assert_eq!(number_format.format(&list), "[1,000, NaN]");
+    }
+
+    #[test]
+    fn uses_locale_separators() {
+        let german = NumberFormat { grouping: true, locale: Locale::German, ..NumberFormat::default() };
+        assert_eq!(german.format(&Value::Float(-1234567.25)), "-1.234.567,25");
+        let list = Value::List(vec![Value::Float(1.5), Value::Integer(BigInt::from(2))]);
+        assert_eq!(german.format(&list), "[1,5; 2]");
+        let french = NumberFormat { notation: Notation::Fixed, digits: 2, grouping: true, ..german };
+        let french = NumberFormat { locale: Locale::French, ..french };
+        assert_eq!(french.format(&Value::Float(1234.5)), "1 234,50");
+        assert_eq!(french.format(&Value::Float(f64::NEG_INFINITY)), "-inf");
+    }
+
+    #[test]
+    fn rounds_uncertain_values_to_their_uncertainty() {
+        let gaussian = |value, error| Value::Uncertain(Uncertain::Gaussian { value, error });
+        assert_eq!(format("auto", Rounding::HalfUp, gaussian(19.62, 0.03999999999999915)), "19.62 ± 0.04");
+        assert_eq!(format("auto", Rounding::HalfUp, gaussian(18.0, 1.2000000000000028)), "18.0 ± 1.2");
+        assert_eq!(format("auto", Rounding::HalfUp, gaussian(1234.5, 150.0)), "1235 ± 150");
+        assert_eq!(format("fixed 1", Rounding::HalfUp, gaussian(9.81, 0.02)), "9.8 ± 0.0");
+        let interval = Value::Uncertain(Uncertain::Interval { low: 9.790000000000001, high: 9.83 });
+        assert_eq!(format("auto", Rounding::HalfUp, interval), "9.79 to 9.83");
+    }
+
+    #[test]
+    fn rejects_invalid_notations() {
+        let mut number_format = NumberFormat::default();
+        assert!(number_format.set_notation("fixed").is_err());
+        assert!(number_format.set_notation("sig 0").is_err());
+        assert!(number_format.set_notation("sci 101").is_err());
+        assert!(number_format.set_notation("auto 3").is_err());
+        assert!(number_format.set_notation("round 3").is_err());
+        assert_eq!(number_format, NumberFormat::default());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::command::check_mutable;
+use crate::complex_evaluate::{separate_vector, FUNCTIONS};
+use crate::evaluate::evaluate;
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::script::KEYWORDS;
+use crate::tokenize::{tokenize_at_depth, Context, WORD_OPERATORS};
+
+// Maximum number of nested user function calls, including recursion.
+const MAX_CALL_DEPTH: usize = 64;
+
+// A user defined function, called like a named complex operator: 'name[value, value, ...]'.
+// Parameters are mutable variables bound to the argument values for the duration of each call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub parameters: Vec<char>,
+    pub body: String,
+}
+
+impl Function {
+    // Formats the function the way parse_definition reads it.
+    pub fn definition(&self, name: &str) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(|parameter| parameter.to_string()).collect();
+        format!("{}({}) = {}", name, parameters.join(", "), self.body)
+    }
+}
+
+// Parses a definition of the form 'name(i, j) = equation'.
+pub fn parse_definition(definition: &str) -> Result<(String, Function), String> {
+    let (signature, body) = definition.split_once('=')
+        .ok_or_else(|| format!("Missing '=' in function definition {}", definition))?;
+    let (name, parameters) = signature.trim().strip_suffix(')')
+        .and_then(|signature| signature.split_once('('))
+        .ok_or_else(|| format!("Expected name(parameters) but found {}", signature.trim()))?;
+
+    let name = name.trim();
+    if name.len() < 2 || !name.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!("{} is not a valid function name. Names must be two or more lowercase letters", name));
+    }
+    // Word operators and 'inf' are read before function names, so functions named after them could not be called.
+    if FUNCTIONS.contains(&name) || KEYWORDS.contains(&name) || WORD_OPERATORS.contains(&name) || name == "inf" {
+        return Err(format!("{} is a reserved name", name));
+    }
+
+    let mut parsed_parameters = Vec::new();
+    for parameter in parameters.split(',').map(str::trim).filter(|parameter| !parameter.is_empty()) {
+        let mut chars = parameter.chars();
+        let variable = match (chars.next(), chars.next()) {
+            (Some(variable), None) => variable,
+            _ => return Err(format!("{} is not a valid parameter", parameter)),
+        };
+        check_mutable(variable)?;
+        if parsed_parameters.contains(&variable) {
+            return Err(format!("Parameter {} is repeated", variable));
+        }
+        parsed_parameters.push(variable);
+    }
+
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(format!("Missing equation for function {}", name));
+    }
+    Ok((name.to_string(), Function { parameters: parsed_parameters, body: body.to_string() }))
+}
+
+// Evaluates a function call from its argument tokens. Arguments are evaluated with the caller's
+// variables, and the body is evaluated with a copy of them where parameters are bound.
+pub fn call_function(name: &str, tokens: &Vec<String>, context: Context,
+                     depth: usize) -> Result<String, String> {
+    let function = context.functions.get(name).ok_or_else(|| format!("Unknown function {}", name))?;
+    if depth >= MAX_CALL_DEPTH {
+        return Err(format!("{} exceeded the maximum call depth of {}", name, MAX_CALL_DEPTH));
+    }
+
+    let arguments = separate_vector(tokens, 0);
+    if arguments.len() != function.parameters.len() {
+        return Err(format!("{} expects {} arguments but received {}",
+                           name, function.parameters.len(), arguments.len()));
+    }
+
+    let mut scope = context.variables.clone();
+    for (parameter, argument) in function.parameters.iter().zip(arguments) {
+        let value = evaluate(infix_to_postfix(&argument))?;
+        scope.insert(*parameter, value.to_string());
+    }
+
+    let tokens = tokenize_at_depth(&function.body, Context { variables: &scope, ..context }, depth + 1)?;
+    Ok(evaluate(infix_to_postfix(&tokens))?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_definitions() {
+        let (name, function) = parse_definition("hyp(i, j) = iHj").unwrap();
+        assert_eq!(name, "hyp");
+        assert_eq!(function.parameters, vec!['i', 'j']);
+        assert_eq!(function.definition(&name), "hyp(i, j) = iHj");
+        assert!(parse_definition("zero() = 0").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_definitions() {
+        assert!(parse_definition("f(i) = i").is_err());
+        assert!(parse_definition("gcd(i) = i").is_err());
+        assert!(parse_definition("and(i) = i").is_err());
+        assert!(parse_definition("not(i) = i").is_err());
+        assert!(parse_definition("inf(i) = i").is_err());
+        assert!(parse_definition("sq(p) = p^2").is_err());
+        assert!(parse_definition("sq(i, i) = i^2").is_err());
+        assert!(parse_definition("sq(i) =").is_err());
+        assert!(parse_definition("sq i = i^2").is_err());
+    }
+}
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_calculator::evaluate::evaluate;
+use rusty_calculator::infix_to_postfix::infix_to_postfix;
+
+// Any token vector must convert to postfix, and its evaluation must return a result or an error.
+fuzz_target!(|tokens: Vec<String>| {
+    let _ = evaluate(infix_to_postfix(&tokens));
+});
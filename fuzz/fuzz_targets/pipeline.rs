@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_calculator::command::change_variable;
+use rusty_calculator::evaluate::evaluate;
+use rusty_calculator::infix_to_postfix::infix_to_postfix;
+use rusty_calculator::session::Session;
+use rusty_calculator::tokenize::tokenize_with_context;
+
+// Solves each ';' separated equation the way the calculator does, including variable changes.
+fuzz_target!(|text: &str| {
+    let mut session = Session::new();
+    for equation in text.split(';') {
+        let equation = equation.trim();
+        if equation.starts_with('_') {
+            let _ = change_variable(equation, &mut session);
+        } else if let Ok(tokens) = tokenize_with_context(equation, session.context()) {
+            if let Ok(result) = evaluate(infix_to_postfix(&tokens)) {
+                session.variables.insert('=', result.to_string());
+            }
+        }
+    }
+});
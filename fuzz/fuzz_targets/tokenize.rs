@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rusty_calculator::command::get_variable_map;
+use rusty_calculator::tokenize::tokenize;
+
+// Any equation must tokenize or return an error without panicking.
+fuzz_target!(|equation: &str| {
+    let _ = tokenize(equation, &get_variable_map());
+});
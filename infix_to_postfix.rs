@@ -3,12 +3,16 @@ fn precedence(operator: &str) -> i8 {
     match operator {
         "!" => 6,
         "^" => 5,
-        "~" => 4,
+        "~" | "±" | "±i" => 4,
         "R" | "L" | "H" | "A" => 3,
         "*" | "/" | "%" | "#" => 2,
         "\\" => 1,
         "+" | "-" => 0,
-        _ => -1,
+        "<" | "<=" | "==" | "!=" | ">" | ">=" => -1,
+        "not" => -2,
+        "and" => -3,
+        "or" => -4,
+        _ => i8::MIN,
     }
 }
 
@@ -19,16 +23,18 @@ pub fn infix_to_postfix(tokens: &Vec<String>) -> Vec<String> {
 
     for token in tokens {
         match token.as_str() {
-            "+" | "-" | "*" | "/" | "^" | "%" | "#" | "\\" | "!" | "R" | "~" | "L" | "H" | "A" => {
+            "+" | "-" | "*" | "/" | "^" | "%" | "#" | "\\" | "!" | "R" | "~" | "L" | "H" | "A"
+            | "±" | "±i" | "<" | "<=" | "==" | "!=" | ">" | ">=" | "and" | "or" => {
                 while let Some(top) = stack.last() {
-                    if top == "(" || precedence(&top) < precedence(token.as_str()) {
+                    if top == "(" || precedence(top) < precedence(token.as_str()) {
                         break;
                     }
                     postfix_expression.push(stack.pop().unwrap());
                 }
                 stack.push(token.to_string());
             },
-            "(" => stack.push(token.to_string()),
+            // Prefix operators with lower precedence than their operand must not pop any operators.
+            "not" | "(" => stack.push(token.to_string()),
             ")" => {
                 while let Some(top) = stack.pop() {
                     if top == "(" {
@@ -42,4 +48,39 @@ pub fn infix_to_postfix(tokens: &Vec<String>) -> Vec<String> {
     }
     postfix_expression.extend(stack.into_iter().rev());
     postfix_expression
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(tokens: &[&str]) -> Vec<String> {
+        let tokens: Vec<String> = tokens.iter().map(|token| token.to_string()).collect();
+        infix_to_postfix(&tokens)
+    }
+
+    #[test]
+    fn applies_precedence() {
+        assert_eq!(convert(&["2", "+", "3", "*", "4", "^", "2", "!"]),
+                   ["2", "3", "4", "2", "!", "^", "*", "+"]);
+        assert_eq!(convert(&["~", "2", "^", "2"]), ["2", "2", "^", "~"]);
+    }
+
+    #[test]
+    fn equal_precedence_is_left_associative() {
+        assert_eq!(convert(&["8", "/", "4", "/", "2"]), ["8", "4", "/", "2", "/"]);
+        assert_eq!(convert(&["2", "^", "3", "^", "2"]), ["2", "3", "^", "2", "^"]);
+    }
+
+    #[test]
+    fn comparisons_and_booleans_have_lowest_precedence() {
+        assert_eq!(convert(&["1", "+", "2", "<", "4", "and", "not", "0", "==", "1", "or", "0"]),
+                   ["1", "2", "+", "4", "<", "0", "1", "==", "not", "and", "0", "or"]);
+        assert_eq!(convert(&["2", "*", "not", "0"]), ["2", "0", "not", "*"]);
+    }
+
+    #[test]
+    fn parenthesis_group_operations() {
+        assert_eq!(convert(&["(", "2", "+", "3", ")", "*", "4"]), ["2", "3", "+", "4", "*"]);
+    }
+}
@@ -0,0 +1,26 @@
+pub mod tokenize;
+pub mod infix_to_postfix;
+pub mod evaluate;
+pub mod command;
+pub mod test;
+pub mod complex_evaluate;
+pub mod number_theory;
+pub mod value;
+pub mod session;
+pub mod editor;
+pub mod output;
+pub mod function;
+pub mod script;
+pub mod format;
+pub mod undo;
+pub mod settings;
+pub mod config;
+pub mod constants;
+pub mod matrix;
+pub mod datetime;
+pub mod finance;
+pub mod uncertainty;
+pub mod probability;
+pub mod series;
+pub mod compile;
+pub mod plot;
@@ -1,65 +1,156 @@
-use std::io;
-use std::io::Write;
+use std::env;
+use std::io::{self, IsTerminal};
 use std::process::ExitCode;
-mod tokenize;
-mod infix_to_postfix;
-mod evaluate;
-mod command;
-mod test;
-mod complex_evaluate;
+use rusty_calculator::{command, config, editor, script, session};
+use rusty_calculator::output::{self, ErrorKind};
+use rusty_calculator::settings::Output;
+use std::path::Path;
 
-// Read operation from user and solve or call command respectively.
+// Run the interactive calculator, or evaluate equations from arguments or piped input.
 fn main() -> ExitCode {
-    let mut variables = command::get_variable_map();
-    let mut settings = command::get_settings_map();
+    let mut arguments: Vec<String> = env::args().skip(1).collect();
+    let json = arguments.iter().any(|argument| argument == "--json");
+    arguments.retain(|argument| argument != "--json");
+
+    match arguments.first().map(String::as_str) {
+        None if io::stdin().is_terminal() => run_interactive(json),
+        None => run_batch(io::stdin().lines().map_while(Result::ok), json),
+        Some("-e") | Some("--eval") => {
+            let mut lines = Vec::new();
+            let mut arguments = arguments.iter();
+            while let Some(argument) = arguments.next() {
+                match (argument.as_str(), arguments.next()) {
+                    ("-e" | "--eval", Some(line)) => lines.push(line.clone()),
+                    _ => {
+                        println!("Error: Expected -e \"equations\". Try --help.");
+                        return ExitCode::FAILURE;
+                    },
+                }
+            }
+            run_batch(lines.into_iter(), json)
+        },
+        Some("-h") | Some("--help") => {
+            print_usage();
+            ExitCode::SUCCESS
+        },
+        Some(file) if arguments.len() == 1 && !file.starts_with('-') => run_file(file, json),
+        Some(argument) => {
+            println!("Error: Unknown argument {}. Try --help.", argument);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+// Read operation from user and solve or call command respectively.
+fn run_interactive(json: bool) -> ExitCode {
+    let mut session = start_session(restore_session(json), json);
+    let mut line_editor = match editor::LineEditor::new() {
+        Ok(line_editor) => line_editor,
+        Err(e) => {
+            println!("Error: Unable to start line editor: {}", e);
+            return ExitCode::FAILURE;
+        },
+    };
     println!("Welcome to Rusty Calculator. Enter your equations below:");
 
     loop {
-        print!("\n> ");
-        io::stdout().flush().unwrap();
-        let mut text = String::new();
-        io::stdin().read_line(&mut text).expect("Failed to read line");
-        if text.ends_with('\n') {
-            text.pop();
-            if text.ends_with('\r') {
-                text.pop();
-            }
+        println!();
+        let text = match line_editor.read_line("> ", &session) {
+            Some(text) => text,
+            None => {
+                // End of input quits like /x.
+                command::execute_command("/x", &mut session);
+                String::new()
+            },
+        };
+        if !text.trim().is_empty() {
+            session.history.push(text.clone());
         }
+        script::run_line(&text, &mut session);
 
-        let equations: Vec<&str> = text.split(";").collect();
-        for equation in equations {
-            let trimmed_eq = equation.trim();
-            if let Some(first) = trimmed_eq.chars().nth(0) {
-                match first {
-                    '/' => command::execute_command(trimmed_eq, &mut variables, &mut settings),
-                    '_' => command::change_variable(trimmed_eq, &mut variables),
-                    _ => {
-                        match tokenize::tokenize(trimmed_eq, &variables) {
-                            Ok(tokens) => {
-                                let expression = infix_to_postfix::infix_to_postfix(&tokens);
-                                if settings["reveal"] {
-                                    println!("Tokens: {:?}", tokens);
-                                    println!("Expression: {:?}", expression);
-                                }
-
-                                match evaluate::evaluate(expression) {
-                                    Ok(result) => {
-                                        println!("Result: {}", result);
-                                        variables.insert('=', result.to_string());
-                                    },
-                                    Err(e) => println!("Error: {}", e),
-                                }
-                            },
-                            Err(e) => println!("Error: {}", e),
-                        }
-                    }
+        if session.quit {
+            if let Some(path) = session::default_path() {
+                if let Err(e) = session::save(&session, &path) {
+                    output::print_error(&path.display().to_string(), ErrorKind::Session, &e, None,
+                                        session.settings.json());
                 }
             }
-        }
-
-        if settings["quit"] {
             println!("Thank you for using Rusty Calculator. Goodbye!");
             return ExitCode::SUCCESS;
         }
     }
-}
\ No newline at end of file
+}
+
+// Solves each line without the welcome message or prompt, starting from default values.
+// Fails if any equation or command fails.
+fn run_batch(lines: impl Iterator<Item = String>, json: bool) -> ExitCode {
+    let mut session = start_session(session::Session::new(), json);
+    let mut success = true;
+    for line in lines {
+        success &= script::run_line(&line, &mut session);
+        if session.quit {
+            break;
+        }
+    }
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+// Runs a script file starting from default values. Fails if any statement fails.
+fn run_file(file: &str, json: bool) -> ExitCode {
+    let mut session = start_session(session::Session::new(), json);
+    match script::run_file(Path::new(file), &mut session) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            output::print_error(file, ErrorKind::Script, &e, None, json);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+// Applies the config file on top of a new or restored session, and JSON output if requested.
+fn start_session(mut session: session::Session, json: bool) -> session::Session {
+    if let Err(e) = config::apply_default(&mut session) {
+        let path = config::default_path().map(|path| path.display().to_string()).unwrap_or_default();
+        output::print_error(&path, ErrorKind::Config, &e, None, json);
+    }
+    if json {
+        session.settings.output = Output::Json;
+    }
+    session
+}
+
+// Loads the session saved by the last /x, falling back to defaults.
+fn restore_session(json: bool) -> session::Session {
+    let path = match session::default_path() {
+        Some(path) if path.exists() => path,
+        _ => return session::Session::new(),
+    };
+    match session::load(&path) {
+        Ok(session) => session,
+        Err(e) => {
+            let message = format!("{}. Starting with default values.", e);
+            output::print_error(&path.display().to_string(), ErrorKind::Session, &message, None, json);
+            session::Session::new()
+        },
+    }
+}
+
+// Prints command line usage.
+fn print_usage() {
+    println!("Usage:\n\
+    rusty_calculator                   -> Start the interactive calculator\n\
+    rusty_calculator -e \"equations\"    -> Solve ';' separated equations and exit\n\
+    rusty_calculator < file            -> Solve each line of file and exit\n\
+    rusty_calculator script.calc       -> Run a script file and exit\n\n\
+    --json prints one JSON object per equation instead of text.\n\
+    -e may be repeated. Equations and commands are entered exactly as in the\n\
+    interactive calculator, starting from default variables and settings\n\
+    and the config file.\n\
+    The exit code is 1 if any equation or command fails, or if a script stops\n\
+    with an error.");
+}
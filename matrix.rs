@@ -0,0 +1,539 @@
+use crate::complex_evaluate::separate_vector;
+use crate::evaluate::{binary_operation, evaluate};
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::fmt;
+
+// Pivots smaller than this fraction of the largest entry are treated as zero.
+const TOLERANCE: f64 = 1E-12;
+
+// Maximum number of QR iterations spent finding one eigenvalue.
+const MAX_EIGEN_ITERATIONS: usize = 1000;
+
+// Largest power a matrix is raised to.
+const MAX_POWER: u32 = 1 << 16;
+
+// Dimensions of a value. Vectors are lists of numbers and matrices are lists of rows,
+// which are lists of numbers of the same length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Scalar,
+    Vector(usize),
+    Matrix(usize, usize),
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Shape::Scalar => write!(f, "number"),
+            Shape::Vector(length) => write!(f, "vector of length {}", length),
+            Shape::Matrix(rows, columns) => write!(f, "{}x{} matrix", rows, columns),
+        }
+    }
+}
+
+// Finds the shape of a value, failing for lists that are neither vectors nor matrices.
+fn shape(value: &Value) -> Result<Shape, String> {
+    let is_list = |item: &Value| matches!(item, Value::List(_));
+    match value {
+        Value::List(items) => match items.first() {
+            Some(Value::List(first)) => {
+                let rectangular = items.iter().all(|item| match item {
+                    Value::List(row) => row.len() == first.len() && !row.iter().any(is_list),
+                    _ => false,
+                });
+                if !rectangular || first.is_empty() {
+                    return Err(format!("{} is not a matrix. Rows must be lists of numbers of the same length",
+                                       value));
+                }
+                Ok(Shape::Matrix(items.len(), first.len()))
+            },
+            _ if items.iter().any(is_list) => Err(format!("{} mixes numbers and lists", value)),
+            _ => Ok(Shape::Vector(items.len())),
+        },
+        _ => Ok(Shape::Scalar),
+    }
+}
+
+// Applies a binary operator where at least one operand is a vector or matrix.
+// '+' and '-' work element by element on equal shapes, numbers are broadcast over every element,
+// '*' multiplies matrices and vectors, and '^' raises a square matrix to an integer power.
+pub fn operation(operator: &str, a: Value, b: Value) -> Result<Value, String> {
+    let (shape_a, shape_b) = (shape(&a)?, shape(&b)?);
+    match (operator, shape_a, shape_b) {
+        ("+" | "-", _, _) if shape_a == shape_b => {
+            let (Value::List(a), Value::List(b)) = (a, b) else { unreachable!() };
+            let items = a.into_iter().zip(b)
+                .map(|(a, b)| binary_operation(operator, a, b))
+                .collect::<Result<Vec<Value>, String>>()?;
+            Ok(Value::List(items))
+        },
+        ("+" | "-" | "*" | "/", _, Shape::Scalar) => map(a, &|item| binary_operation(operator, item, b.clone())),
+        ("+" | "-" | "*", Shape::Scalar, _) => map(b, &|item| binary_operation(operator, a.clone(), item)),
+        ("*", _, _) => multiply(&a, shape_a, &b, shape_b),
+        ("^", Shape::Matrix(rows, columns), Shape::Scalar) if rows == columns => power(a, &b),
+        ("+", _, _) => Err(format!("Cannot add a {} and a {}", shape_a, shape_b)),
+        ("-", _, _) => Err(format!("Cannot subtract a {} from a {}", shape_b, shape_a)),
+        _ => Err(format!("{} is not defined for a {} and a {}", operator, shape_a, shape_b)),
+    }
+}
+
+// Applies a unary operation to every number of a value.
+pub fn map(value: Value, operation: &dyn Fn(Value) -> Result<Value, String>) -> Result<Value, String> {
+    match value {
+        Value::List(items) => items.into_iter()
+            .map(|item| map(item, operation))
+            .collect::<Result<Vec<Value>, String>>()
+            .map(Value::List),
+        value => operation(value),
+    }
+}
+
+// Multiplies matrices and vectors. Vectors are columns on the right and rows on the left,
+// and two vectors give their dot product.
+fn multiply(a: &Value, shape_a: Shape, b: &Value, shape_b: Shape) -> Result<Value, String> {
+    let mismatch = || Err(format!("Cannot multiply a {} by a {}", shape_a, shape_b));
+    let (a, b) = (items(a), items(b));
+    match (shape_a, shape_b) {
+        (Shape::Vector(length_a), Shape::Vector(length_b)) if length_a == length_b => dot(a, b),
+        (Shape::Matrix(_, columns), Shape::Vector(length)) if columns == length => {
+            a.iter().map(|row| dot(items(row), b)).collect::<Result<Vec<Value>, String>>().map(Value::List)
+        },
+        (Shape::Vector(length), Shape::Matrix(rows, _)) if length == rows => {
+            let columns = transpose_items(b);
+            columns.iter().map(|column| dot(a, column)).collect::<Result<Vec<Value>, String>>().map(Value::List)
+        },
+        (Shape::Matrix(_, columns), Shape::Matrix(rows, _)) if columns == rows => {
+            let columns = transpose_items(b);
+            a.iter().map(|row| {
+                columns.iter().map(|column| dot(items(row), column))
+                    .collect::<Result<Vec<Value>, String>>()
+                    .map(Value::List)
+            }).collect::<Result<Vec<Value>, String>>().map(Value::List)
+        },
+        _ => mismatch(),
+    }
+}
+
+// Raises a square matrix to an integer power. Negative powers use the inverse.
+fn power(matrix: Value, exponent: &Value) -> Result<Value, String> {
+    let exponent = exponent.to_integer().map_err(|_| "Matrix powers must be integers".to_string())?;
+    let mut base = if exponent.is_negative() { inverse(&matrix).map_err(str::to_string)? } else { matrix };
+    let mut exponent = exponent.abs().to_u32().filter(|exponent| *exponent <= MAX_POWER)
+        .ok_or_else(|| format!("Matrix powers are limited to {}", MAX_POWER))?;
+
+    let size = items(&base).len();
+    let mut result = Value::List((0..size).map(|row| {
+        Value::List((0..size).map(|column| Value::from_bool(row == column)).collect())
+    }).collect());
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = operation("*", result, base.clone())?;
+        }
+        exponent /= 2;
+        if exponent > 0 {
+            base = operation("*", base.clone(), base)?;
+        }
+    }
+    Ok(result)
+}
+
+// Computes the sum of products of two equal length lists of numbers.
+fn dot(a: &[Value], b: &[Value]) -> Result<Value, String> {
+    a.iter().zip(b).try_fold(Value::Integer(BigInt::zero()), |sum, (a, b)| {
+        binary_operation("+", sum, binary_operation("*", a.clone(), b.clone())?)
+    })
+}
+
+// Returns the items of a list, or no items for a number.
+fn items(value: &Value) -> &[Value] {
+    match value {
+        Value::List(items) => items,
+        _ => &[],
+    }
+}
+
+// Returns the columns of a matrix as lists.
+fn transpose_items(rows: &[Value]) -> Vec<Vec<Value>> {
+    let columns = rows.first().map_or(0, |row| items(row).len());
+    (0..columns).map(|column| rows.iter().map(|row| items(row)[column].clone()).collect()).collect()
+}
+
+// Evaluates comma separated values.
+fn values(tokens: &Vec<String>) -> Result<Vec<Value>, &'static str> {
+    separate_vector(tokens, 0).iter()
+        .map(|equation_tokens| evaluate(infix_to_postfix(equation_tokens)).map_err(|_| "Could not evaluate list value"))
+        .collect()
+}
+
+// Evaluates a single matrix value and returns its rows as floats.
+fn matrix_value(tokens: &Vec<String>, name: &'static str) -> Result<(Value, Vec<Vec<f64>>), &'static str> {
+    let matrix = match values(tokens)?.as_slice() {
+        [matrix] => matrix.clone(),
+        _ => return Err(name),
+    };
+    let rows = float_rows(&matrix).ok_or(name)?;
+    Ok((matrix, rows))
+}
+
+// Converts a matrix to rows of floats.
+fn float_rows(matrix: &Value) -> Option<Vec<Vec<f64>>> {
+    if !matches!(shape(matrix), Ok(Shape::Matrix(_, _))) {
+        return None;
+    }
+    items(matrix).iter()
+        .map(|row| items(row).iter().map(|item| item.to_f64().ok()).collect())
+        .collect()
+}
+
+// Converts rows of floats to a matrix value.
+fn float_matrix(rows: Vec<Vec<f64>>) -> Value {
+    Value::List(rows.into_iter().map(|row| Value::List(row.into_iter().map(Value::Float).collect())).collect())
+}
+
+// Builds a list from comma separated values, as in '[1, 2]' or '[[1, 2], [3, 4]]'.
+pub fn list(tokens: &Vec<String>) -> Result<String, &str> {
+    Ok(Value::List(values(tokens)?).to_string())
+}
+
+// Transposes the given matrix as [matrix]. Vectors become a single column.
+pub fn transpose(tokens: &Vec<String>) -> Result<String, &str> {
+    let matrix = match values(tokens)?.as_slice() {
+        [matrix] => matrix.clone(),
+        _ => return Err("transpose takes a single matrix or vector"),
+    };
+    let rows = match shape(&matrix) {
+        Ok(Shape::Matrix(_, _)) => transpose_items(items(&matrix)),
+        Ok(Shape::Vector(_)) => items(&matrix).iter().map(|item| vec![item.clone()]).collect(),
+        _ => return Err("transpose takes a single matrix or vector"),
+    };
+    Ok(Value::List(rows.into_iter().map(Value::List).collect()).to_string())
+}
+
+// Computes the determinant of the given square matrix as [matrix].
+// Integer matrices have exact determinants.
+pub fn determinant(tokens: &Vec<String>) -> Result<String, &str> {
+    let (matrix, mut rows) = matrix_value(tokens, "det takes a single square matrix")?;
+    if rows.len() != rows[0].len() {
+        return Err("det takes a single square matrix");
+    }
+    let integers: Option<Vec<Vec<BigInt>>> = items(&matrix).iter()
+        .map(|row| items(row).iter().map(|item| match item {
+            Value::Integer(integer) => Some(integer.clone()),
+            _ => None,
+        }).collect())
+        .collect();
+    if let Some(integers) = integers {
+        return Ok(integer_determinant(integers).to_string());
+    }
+
+    let size = rows.len();
+    let (rank, swapped) = row_echelon(&mut rows);
+    if rank < size {
+        return Ok(0.0.to_string());
+    }
+    let product: f64 = (0..size).map(|index| rows[index][index]).product();
+    Ok(if swapped { -product } else { product }.to_string())
+}
+
+// Computes the inverse of the given square matrix as [matrix].
+pub fn inverse_matrix(tokens: &Vec<String>) -> Result<String, &str> {
+    let (matrix, _) = matrix_value(tokens, "inv takes a single square matrix")?;
+    Ok(inverse(&matrix)?.to_string())
+}
+
+// Computes the rank of the given matrix as [matrix].
+pub fn rank(tokens: &Vec<String>) -> Result<String, &str> {
+    let (_, mut rows) = matrix_value(tokens, "rank takes a single matrix")?;
+    Ok(row_echelon(&mut rows).0.to_string())
+}
+
+// Solves Ax = b for x as [A, b], where A is a square matrix and b a vector.
+pub fn solve(tokens: &Vec<String>) -> Result<String, &str> {
+    let arguments = values(tokens)?;
+    let usage = "solve takes a square matrix A and a vector b as [A, b]";
+    let (rows, b) = match arguments.as_slice() {
+        [matrix, Value::List(b)] => (float_rows(matrix).ok_or(usage)?, b),
+        _ => return Err(usage),
+    };
+    if rows.len() != rows[0].len() || rows.len() != b.len() {
+        return Err("solve needs a square matrix with as many rows as b has values");
+    }
+
+    let mut augmented = Vec::new();
+    for (mut row, value) in rows.into_iter().zip(b) {
+        row.push(value.to_f64().map_err(|_| usage)?);
+        augmented.push(row);
+    }
+    let size = augmented.len();
+    reduce_augmented(&mut augmented, size)?;
+    Ok(Value::List(augmented.iter().map(|row| Value::Float(row[size])).collect()).to_string())
+}
+
+// Computes the real eigenvalues of the given square matrix as [matrix], largest first.
+pub fn eigenvalues(tokens: &Vec<String>) -> Result<String, &str> {
+    let (_, rows) = matrix_value(tokens, "eig takes a single square matrix")?;
+    if rows.len() != rows[0].len() {
+        return Err("eig takes a single square matrix");
+    }
+    let mut values = real_eigenvalues(rows)?;
+    values.sort_by(|a, b| b.total_cmp(a));
+    Ok(Value::List(values.into_iter().map(Value::Float).collect()).to_string())
+}
+
+// Inverts a square matrix with Gauss-Jordan elimination.
+fn inverse(matrix: &Value) -> Result<Value, &'static str> {
+    let rows = float_rows(matrix).filter(|rows| rows.len() == rows[0].len())
+        .ok_or("Only square matrices have inverses")?;
+    let size = rows.len();
+    let mut augmented: Vec<Vec<f64>> = rows.into_iter().enumerate().map(|(index, mut row)| {
+        row.extend((0..size).map(|column| if column == index { 1.0 } else { 0.0 }));
+        row
+    }).collect();
+    reduce_augmented(&mut augmented, size)?;
+    Ok(float_matrix(augmented.into_iter().map(|row| row[size..].to_vec()).collect()))
+}
+
+// Computes the determinant of an integer matrix exactly with fraction free Bareiss elimination.
+fn integer_determinant(mut rows: Vec<Vec<BigInt>>) -> BigInt {
+    let size = rows.len();
+    let mut sign = BigInt::one();
+    let mut previous = BigInt::one();
+    for k in 0..size {
+        if rows[k][k].is_zero() {
+            match (k + 1..size).find(|&row| !rows[row][k].is_zero()) {
+                Some(row) => {
+                    rows.swap(k, row);
+                    sign = -sign;
+                },
+                None => return BigInt::zero(),
+            }
+        }
+        for i in k + 1..size {
+            for j in k + 1..size {
+                rows[i][j] = (&rows[i][j] * &rows[k][k] - &rows[i][k] * &rows[k][j]) / &previous;
+            }
+        }
+        previous = rows[k][k].clone();
+    }
+    sign * &rows[size - 1][size - 1]
+}
+
+// Reduces rows to row echelon form with partial pivoting.
+// Returns the number of pivots and whether an odd number of rows were swapped.
+fn row_echelon(rows: &mut [Vec<f64>]) -> (usize, bool) {
+    let columns = rows[0].len();
+    let scale = rows.iter().flatten().fold(0.0, |max: f64, value| max.max(value.abs()));
+    let mut rank = 0;
+    let mut swapped = false;
+    for column in 0..columns {
+        if rank == rows.len() {
+            break;
+        }
+        let pivot = (rank..rows.len()).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))
+            .unwrap_or(rank);
+        if rows[pivot][column].abs() <= TOLERANCE * scale {
+            continue;
+        }
+        if pivot != rank {
+            rows.swap(pivot, rank);
+            swapped = !swapped;
+        }
+        let pivot_row = rows[rank].clone();
+        for row in rows[rank + 1..].iter_mut() {
+            let factor = row[column] / pivot_row[column];
+            for (value, pivot_value) in row[column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= factor * pivot_value;
+            }
+        }
+        rank += 1;
+    }
+    (rank, swapped)
+}
+
+// Reduces an augmented matrix [A | B], where A is size x size, to [I | X] so that AX = B.
+fn reduce_augmented(rows: &mut [Vec<f64>], size: usize) -> Result<(), &'static str> {
+    let scale = rows.iter().flat_map(|row| &row[..size]).fold(0.0, |max: f64, value| max.max(value.abs()));
+    for column in 0..size {
+        let pivot = (column..size).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))
+            .unwrap_or(column);
+        if rows[pivot][column].abs() <= TOLERANCE * scale {
+            return Err("Matrix is singular");
+        }
+        rows.swap(pivot, column);
+        let divisor = rows[column][column];
+        for value in rows[column].iter_mut() {
+            *value /= divisor;
+        }
+        let pivot_row = rows[column].clone();
+        for (index, row) in rows.iter_mut().enumerate() {
+            let factor = row[column];
+            if index != column && factor != 0.0 {
+                for (value, pivot_value) in row.iter_mut().zip(&pivot_row) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Finds eigenvalues with the shifted QR algorithm, deflating converged values from the bottom.
+// Fails when a pair of eigenvalues is complex.
+fn real_eigenvalues(mut rows: Vec<Vec<f64>>) -> Result<Vec<f64>, &'static str> {
+    let negligible = |value: f64, a: f64, b: f64| value.abs() <= f64::EPSILON * (a.abs() + b.abs());
+    let mut values = Vec::new();
+    let mut size = rows.len();
+    let mut iterations = 0;
+
+    while size > 0 {
+        let last = size - 1;
+        if size == 1 || negligible(rows[last][last - 1], rows[last][last], rows[last - 1][last - 1]) {
+            values.push(rows[last][last]);
+            size -= 1;
+            iterations = 0;
+            continue;
+        }
+
+        let pair = pair_eigenvalues(rows[last - 1][last - 1], rows[last - 1][last], rows[last][last - 1],
+                                    rows[last][last]);
+        if size == 2 || negligible(rows[last - 1][last - 2], rows[last - 1][last - 1], rows[last - 2][last - 2]) {
+            let (first, second) = pair.ok_or("Complex eigenvalues are not supported")?;
+            values.extend([first, second]);
+            size -= 2;
+            iterations = 0;
+            continue;
+        }
+
+        iterations += 1;
+        if iterations > MAX_EIGEN_ITERATIONS {
+            return Err("Eigenvalues did not converge");
+        }
+        // Shift by the eigenvalue of the trailing 2x2 block closest to the last diagonal value.
+        let corner = rows[last][last];
+        let shift = pair.map_or(corner, |(first, second)| {
+            if (first - corner).abs() < (second - corner).abs() { first } else { second }
+        });
+        qr_step(&mut rows, size, shift);
+    }
+    Ok(values)
+}
+
+// Computes the eigenvalues of the 2x2 matrix [[a, b], [c, d]], or None if they are complex.
+fn pair_eigenvalues(a: f64, b: f64, c: f64, d: f64) -> Option<(f64, f64)> {
+    let half_trace = (a + d) / 2.0;
+    let determinant = a * d - b * c;
+    let discriminant = half_trace * half_trace - determinant;
+    if discriminant < -TOLERANCE * (half_trace * half_trace + determinant.abs()) {
+        return None;
+    }
+    let root = discriminant.max(0.0).sqrt();
+    Some((half_trace + root, half_trace - root))
+}
+
+// Replaces the leading size x size block A with RQ + shift I, where QR = A - shift I.
+// The factorization uses Givens rotations.
+fn qr_step(rows: &mut [Vec<f64>], size: usize, shift: f64) {
+    let mut block: Vec<Vec<f64>> = (0..size).map(|i| {
+        (0..size).map(|j| rows[i][j] - if i == j { shift } else { 0.0 }).collect()
+    }).collect();
+
+    let mut rotations = Vec::new();
+    for j in 0..size {
+        for i in j + 1..size {
+            let (x, y) = (block[j][j], block[i][j]);
+            if y == 0.0 {
+                continue;
+            }
+            let radius = x.hypot(y);
+            let (cos, sin) = (x / radius, y / radius);
+            let (upper, lower) = block.split_at_mut(i);
+            for (p, q) in upper[j].iter_mut().zip(lower[0].iter_mut()) {
+                (*p, *q) = (cos * *p + sin * *q, cos * *q - sin * *p);
+            }
+            rotations.push((j, i, cos, sin));
+        }
+    }
+    for (j, i, cos, sin) in rotations {
+        for row in block.iter_mut() {
+            let (p, q) = (row[j], row[i]);
+            row[j] = cos * p + sin * q;
+            row[i] = cos * q - sin * p;
+        }
+    }
+
+    for (i, row) in block.into_iter().enumerate() {
+        for (j, value) in row.into_iter().enumerate() {
+            rows[i][j] = value + if i == j { shift } else { 0.0 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> Value {
+        Value::parse(text).unwrap()
+    }
+
+    fn tokens(text: &str) -> Vec<String> {
+        vec![text.to_string()]
+    }
+
+    #[test]
+    fn operators_follow_shapes() {
+        let a = value("[[1, 2], [3, 4]]");
+        assert_eq!(operation("*", a.clone(), a.clone()), Ok(value("[[7, 10], [15, 22]]")));
+        assert_eq!(operation("*", a.clone(), value("[1, 1]")), Ok(value("[3, 7]")));
+        assert_eq!(operation("*", value("[1, 1]"), a.clone()), Ok(value("[4, 6]")));
+        assert_eq!(operation("*", value("[1, 2, 3]"), value("[4, 5, 6]")), Ok(value("32")));
+        assert_eq!(operation("-", a.clone(), value("1")), Ok(value("[[0, 1], [2, 3]]")));
+        assert_eq!(operation("*", value("2"), a.clone()), Ok(value("[[2, 4], [6, 8]]")));
+        assert_eq!(operation("^", a.clone(), value("3")), Ok(value("[[37, 54], [81, 118]]")));
+        assert_eq!(operation("^", a.clone(), value("0")), Ok(value("[[1, 0], [0, 1]]")));
+    }
+
+    #[test]
+    fn dimension_mismatches_are_errors() {
+        let a = value("[[1, 2, 3], [4, 5, 6]]");
+        assert_eq!(operation("*", a.clone(), a.clone()),
+                   Err("Cannot multiply a 2x3 matrix by a 2x3 matrix".to_string()));
+        assert_eq!(operation("+", a.clone(), value("[1, 2]")),
+                   Err("Cannot add a 2x3 matrix and a vector of length 2".to_string()));
+        assert!(operation("^", a, value("2")).is_err());
+        assert!(operation("+", value("[[1], [2, 3]]"), value("1")).is_err());
+        assert!(operation("/", value("1"), value("[1]")).is_err());
+    }
+
+    #[test]
+    fn computes_linear_algebra_functions() {
+        assert_eq!(determinant(&tokens("[[2, 0, 1], [1, 3, 2], [1, 1, 2]]")), Ok("6".to_string()));
+        assert_eq!(determinant(&tokens("[[0.5, 1], [1, 2]]")), Ok("0".to_string()));
+        assert_eq!(inverse_matrix(&tokens("[[4, 2], [2, 2]]")), Ok("[[0.5, -0.5], [-0.5, 1]]".to_string()));
+        assert_eq!(inverse_matrix(&tokens("[[1, 2], [2, 4]]")), Err("Matrix is singular"));
+        assert_eq!(rank(&tokens("[[1, 2, 3], [2, 4, 6], [1, 0, 1]]")), Ok("2".to_string()));
+        assert_eq!(transpose(&tokens("[[1, 2, 3], [4, 5, 6]]")), Ok("[[1, 4], [2, 5], [3, 6]]".to_string()));
+
+        let tokens = vec!["[[2, 1], [1, 3]]".to_string(), ",".to_string(), "[3, 5]".to_string()];
+        assert_eq!(solve(&tokens), Ok("[0.8, 1.4]".to_string()));
+    }
+
+    #[test]
+    fn finds_real_eigenvalues() {
+        let close = |text: &str, expected: &[f64]| {
+            let found = value(&eigenvalues(&tokens(text)).unwrap());
+            let found: Vec<f64> = items(&found).iter().map(|item| item.to_f64().unwrap()).collect();
+            found.len() == expected.len() && found.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1E-9)
+        };
+        assert!(close("[[2, 1], [1, 2]]", &[3.0, 1.0]));
+        assert!(close("[[2, 0, 0], [0, 3, 4], [0, 4, 9]]", &[11.0, 2.0, 1.0]));
+        assert!(close("[[1, 2, 3], [0, 4, 5], [0, 0, 6]]", &[6.0, 4.0, 1.0]));
+        assert!(close("[[4, 1], [2, 3]]", &[5.0, 2.0]));
+        // A symmetric matrix whose eigenvalues are 2 +- sqrt(2) and 2.
+        assert!(close("[[2, 1, 0], [1, 2, 1], [0, 1, 2]]", &[2.0 + 2f64.sqrt(), 2.0, 2.0 - 2f64.sqrt()]));
+        assert_eq!(eigenvalues(&tokens("[[0, -1], [1, 0]]")), Err("Complex eigenvalues are not supported"));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::complex_evaluate::separate_vector;
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::evaluate::evaluate;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+// Bases for the Miller-Rabin test, which is deterministic with them below DETERMINISTIC_LIMIT (3.2E23).
+// Larger values must also pass a strong Lucas test, making it the Baillie-PSW test.
+const WITNESSES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+const DETERMINISTIC_LIMIT: &str = "318665857834031151167461";
+const TRIAL_DIVISION_LIMIT: u32 = 1000;
+const RHO_ATTEMPTS: u32 = 2;
+const RHO_BATCH: u32 = 128;
+const RHO_ITERATION_LIMIT: u32 = 1 << 19;
+const MAX_SELECTION: u64 = 100_000;
+
+// Evaluates comma separated values as exact integers.
+fn integer_values(tokens: &Vec<String>) -> Result<Vec<BigInt>, &'static str> {
+    separate_vector(tokens, 0).iter().map(|equation_tokens| {
+        match evaluate(infix_to_postfix(equation_tokens)) {
+            Ok(result) => result.to_integer().map_err(|_| "Number theory values must be integers"),
+            Err(_) => Err("Could not evaluate number theory value"),
+        }
+    }).collect()
+}
+
+// Computes the greatest common divisor of given tokens as [value,value,...].
+pub fn gcd(tokens: &Vec<String>) -> Result<String, &str> {
+    let values = integer_values(tokens)?;
+    if values.is_empty() {
+        return Err("Missing gcd values");
+    }
+    let result = values.iter().fold(BigInt::zero(), |acc, value| acc.gcd(value));
+    Ok(result.to_string())
+}
+
+// Computes the least common multiple of given tokens as [value,value,...].
+pub fn lcm(tokens: &Vec<String>) -> Result<String, &str> {
+    let values = integer_values(tokens)?;
+    if values.is_empty() {
+        return Err("Missing lcm values");
+    }
+    let result = values.iter().fold(BigInt::one(), |acc, value| acc.lcm(value));
+    Ok(result.to_string())
+}
+
+// Returns 1 if the given token value is prime and 0 otherwise as [value].
+pub fn is_prime(tokens: &Vec<String>) -> Result<String, &str> {
+    let values = integer_values(tokens)?;
+    match values.as_slice() {
+        [value] => Ok(if probable_prime(value) { "1" } else { "0" }.to_string()),
+        [] => Err("Missing isprime value"),
+        _ => Err("isprime takes a single value"),
+    }
+}
+
+// Computes the prime factorisation of the given token value as [value].
+// Negative values include a factor of -1.
+pub fn factor(tokens: &Vec<String>) -> Result<String, &str> {
+    let values = integer_values(tokens)?;
+    let value = match values.as_slice() {
+        [value] => value,
+        [] => return Err("Missing factor value"),
+        _ => return Err("factor takes a single value"),
+    };
+    if value.is_zero() {
+        return Err("Cannot factor 0");
+    }
+
+    let mut factors: Vec<BigInt> = Vec::new();
+    if value.is_negative() {
+        factors.push(-BigInt::one());
+    }
+    prime_factors(value.abs(), &mut factors)?;
+    factors.sort();
+    Ok(Value::List(factors.into_iter().map(Value::Integer).collect()).to_string())
+}
+
+// Computes modular exponentiation of given tokens as [base,exponent,modulus].
+// Negative exponents use the modular inverse of the base.
+pub fn modpow(tokens: &Vec<String>) -> Result<String, &str> {
+    let values = integer_values(tokens)?;
+    let (base, exponent, modulus) = match values.as_slice() {
+        [base, exponent, modulus] => (base, exponent, modulus),
+        [] | [_] | [_, _] => return Err("Missing modpow base, exponent, or modulus"),
+        _ => return Err("modpow takes a base, exponent, and modulus"),
+    };
+    if !modulus.is_positive() {
+        return Err("Modulus must be positive");
+    }
+
+    let base = if exponent.is_negative() {
+        inverse(base, modulus).ok_or("Base has no modular inverse")?
+    } else {
+        base.mod_floor(modulus)
+    };
+    Ok(base.modpow(&exponent.abs(), modulus).to_string())
+}
+
+// Computes the modular inverse of given tokens as [value,modulus].
+pub fn modinv(tokens: &Vec<String>) -> Result<String, &str> {
+    let values = integer_values(tokens)?;
+    let (value, modulus) = match values.as_slice() {
+        [value, modulus] => (value, modulus),
+        [] | [_] => return Err("Missing modinv value or modulus"),
+        _ => return Err("modinv takes a value and modulus"),
+    };
+    if !modulus.is_positive() {
+        return Err("Modulus must be positive");
+    }
+
+    match inverse(value, modulus) {
+        Some(result) => Ok(result.to_string()),
+        None => Err("Value has no modular inverse"),
+    }
+}
+
+// Computes the number of combinations of given tokens as [n,r].
+pub fn combinations(tokens: &Vec<String>) -> Result<String, &str> {
+    let (n, r) = selection_values(tokens)?;
+    if r > n {
+        return Ok("0".to_string());
+    }
+    let r = (&n - &r).min(r);
+    let mut result = BigInt::one();
+    for i in 0..r.to_u64().unwrap_or(0) {
+        result = result * (&n - i) / (i + 1);
+    }
+    Ok(result.to_string())
+}
+
+// Computes the number of permutations of given tokens as [n,r].
+pub fn permutations(tokens: &Vec<String>) -> Result<String, &str> {
+    let (n, r) = selection_values(tokens)?;
+    if r > n {
+        return Ok("0".to_string());
+    }
+    let mut result = BigInt::one();
+    for i in 0..r.to_u64().unwrap_or(0) {
+        result *= &n - i;
+    }
+    Ok(result.to_string())
+}
+
+// Evaluates and validates the n and r values of combinations and permutations.
+fn selection_values(tokens: &Vec<String>) -> Result<(BigInt, BigInt), &'static str> {
+    let values = integer_values(tokens)?;
+    let (n, r) = match values.as_slice() {
+        [n, r] => (n.clone(), r.clone()),
+        [] | [_] => return Err("Missing n or r value"),
+        _ => return Err("Selections take an n and r value"),
+    };
+    if n.is_negative() || r.is_negative() {
+        return Err("Selection values must not be negative");
+    }
+    if r.to_u64().is_none_or(|r| r > MAX_SELECTION) {
+        return Err("Selection r value is too large");
+    }
+    Ok((n, r))
+}
+
+// Computes the modular inverse of a value, if one exists.
+fn inverse(value: &BigInt, modulus: &BigInt) -> Option<BigInt> {
+    let extended = value.mod_floor(modulus).extended_gcd(modulus);
+    if !extended.gcd.is_one() {
+        return None;
+    }
+    Some(extended.x.mod_floor(modulus))
+}
+
+// Miller-Rabin primality test, followed by a strong Lucas test at or above DETERMINISTIC_LIMIT.
+fn probable_prime(n: &BigInt) -> bool {
+    if *n < BigInt::from(2) {
+        return false;
+    }
+    for witness in WITNESSES {
+        let witness = BigInt::from(witness);
+        if *n == witness {
+            return true;
+        }
+        if (n % &witness).is_zero() {
+            return false;
+        }
+    }
+
+    let n_minus_one: BigInt = n - 1;
+    let shift = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> shift;
+    'witness: for witness in WITNESSES {
+        let mut x = BigInt::from(witness).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..shift {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    *n < DETERMINISTIC_LIMIT.parse::<BigInt>().unwrap_or_default() || strong_lucas_prime(n)
+}
+
+// Strong Lucas probable prime test with Selfridge's parameters, for odd n above the witnesses.
+fn strong_lucas_prime(n: &BigInt) -> bool {
+    let root = n.sqrt();
+    if &root * &root == *n {
+        return false;
+    }
+    // D is the first of 5, -7, 9, -11, ... with Jacobi symbol (D/n) = -1. P = 1 and Q = (1 - D) / 4.
+    let mut d = BigInt::from(5);
+    loop {
+        match jacobi(&d, n) {
+            -1 => break,
+            0 if d.abs() != *n => return false,
+            _ => d = if d.is_positive() { -(d + 2u32) } else { 2u32 - d },
+        }
+    }
+    let q: BigInt = (1 - &d) / 4;
+
+    // Halves a value modulo odd n.
+    let half = |x: BigInt| -> BigInt { if x.is_odd() { (x + n) / 2u32 } else { x / 2u32 }.mod_floor(n) };
+    let n_plus_one: BigInt = n + 1;
+    let shift = n_plus_one.trailing_zeros().unwrap_or(0);
+    let k = &n_plus_one >> shift;
+
+    // Computes U(k), V(k), and Q^k modulo n from the highest bit of k down.
+    let (mut u, mut v, mut q_k) = (BigInt::one(), BigInt::one(), q.mod_floor(n));
+    for bit in (0..k.bits() - 1).rev() {
+        u = (&u * &v).mod_floor(n);
+        v = (&v * &v - &q_k * 2u32).mod_floor(n);
+        q_k = (&q_k * &q_k).mod_floor(n);
+        if k.bit(bit) {
+            (u, v) = (half(&u + &v), half(&d * &u + &v));
+            q_k = (&q_k * &q).mod_floor(n);
+        }
+    }
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..shift {
+        v = (&v * &v - &q_k * 2u32).mod_floor(n);
+        if v.is_zero() {
+            return true;
+        }
+        q_k = (&q_k * &q_k).mod_floor(n);
+    }
+    false
+}
+
+// Computes the Jacobi symbol (a/n) for odd positive n.
+fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+    let (mut a, mut n) = (a.mod_floor(n), n.clone());
+    let mut result = 1;
+    while !a.is_zero() {
+        while a.is_even() {
+            a >>= 1;
+            let remainder = (&n % 8u32).to_u32().unwrap_or(0);
+            if remainder == 3 || remainder == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if (&a % 4u32).to_u32() == Some(3) && (&n % 4u32).to_u32() == Some(3) {
+            result = -result;
+        }
+        a = a.mod_floor(&n);
+    }
+    if n.is_one() { result } else { 0 }
+}
+
+// Pushes the prime factors of a positive value onto factors.
+fn prime_factors(mut n: BigInt, factors: &mut Vec<BigInt>) -> Result<(), &'static str> {
+    for divisor in 2..=TRIAL_DIVISION_LIMIT {
+        let divisor = BigInt::from(divisor);
+        if &divisor * &divisor > n {
+            break;
+        }
+        while (&n % &divisor).is_zero() {
+            factors.push(divisor.clone());
+            n /= &divisor;
+        }
+    }
+    split_factors(n, factors)
+}
+
+// Recursively splits a value without small factors using Pollard's rho.
+fn split_factors(n: BigInt, factors: &mut Vec<BigInt>) -> Result<(), &'static str> {
+    if n.is_one() {
+        return Ok(());
+    }
+    if probable_prime(&n) {
+        factors.push(n);
+        return Ok(());
+    }
+    let divisor = pollard_rho(&n).ok_or("Value is too large to factor")?;
+    let quotient = &n / &divisor;
+    split_factors(divisor, factors)?;
+    split_factors(quotient, factors)
+}
+
+// Finds a non-trivial divisor of a composite value using Brent's variant of Pollard's rho.
+fn pollard_rho(n: &BigInt) -> Option<BigInt> {
+    for c in 1..=RHO_ATTEMPTS {
+        let step = |x: &BigInt| (x * x + c) % n;
+        let mut y = BigInt::from(2);
+        let mut x = y.clone();
+        let mut saved_y = y.clone();
+        let mut divisor = BigInt::one();
+        let mut product = BigInt::one();
+        let mut length: u32 = 1;
+
+        while divisor.is_one() && length <= RHO_ITERATION_LIMIT {
+            x = y.clone();
+            for _ in 0..length {
+                y = step(&y);
+            }
+            // Multiply differences together to take one gcd per batch.
+            let mut completed = 0;
+            while completed < length && divisor.is_one() {
+                saved_y = y.clone();
+                for _ in 0..RHO_BATCH.min(length - completed) {
+                    y = step(&y);
+                    product = product * (&x - &y).abs() % n;
+                }
+                divisor = product.gcd(n);
+                completed += RHO_BATCH;
+            }
+            length *= 2;
+        }
+
+        if divisor == *n {
+            // The batch overshot. Repeat it one step at a time.
+            for _ in 0..RHO_BATCH {
+                saved_y = step(&saved_y);
+                divisor = (&x - &saved_y).abs().gcd(n);
+                if !divisor.is_one() {
+                    break;
+                }
+            }
+        }
+        if !divisor.is_one() && divisor != *n {
+            return Some(divisor);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_primes() {
+        let primes: Vec<u32> = (0..60).filter(|n| probable_prime(&BigInt::from(*n))).collect();
+        assert_eq!(primes, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59]);
+        // Carmichael numbers fool the Fermat test but not Miller-Rabin.
+        assert!(!probable_prime(&BigInt::from(561)));
+        assert!(!probable_prime(&BigInt::from(3215031751u64)));
+        assert!(probable_prime(&((BigInt::one() << 127) - 1)));
+        // Strong pseudoprimes to every witness are caught by the Lucas test.
+        assert!(!probable_prime(&"3317044064679887385961981".parse::<BigInt>().unwrap()));
+        assert!(!probable_prime(&"318665857834031151167461".parse::<BigInt>().unwrap()));
+        assert!(probable_prime(&((BigInt::one() << 89) - 1)));
+        assert!(!probable_prime(&(((BigInt::one() << 89) - 1) * ((BigInt::one() << 61) - 1))));
+    }
+
+    #[test]
+    fn computes_jacobi_symbols() {
+        let symbols: Vec<i32> = (0..8).map(|a| jacobi(&BigInt::from(a), &BigInt::from(15))).collect();
+        assert_eq!(symbols, [0, 1, 1, 0, 1, 0, 0, -1]);
+        assert_eq!(jacobi(&BigInt::from(-7), &BigInt::from(11)), 1);
+    }
+
+    #[test]
+    fn factors_large_values() {
+        let mut factors = Vec::new();
+        prime_factors(BigInt::from(1_000_000_007u64) * BigInt::from(998_244_353u64), &mut factors).unwrap();
+        factors.sort();
+        assert_eq!(factors, [BigInt::from(998_244_353u64), BigInt::from(1_000_000_007u64)]);
+    }
+
+    #[test]
+    fn computes_modular_inverses() {
+        assert_eq!(inverse(&BigInt::from(3), &BigInt::from(11)), Some(BigInt::from(4)));
+        assert_eq!(inverse(&BigInt::from(-3), &BigInt::from(11)), Some(BigInt::from(7)));
+        assert_eq!(inverse(&BigInt::from(6), &BigInt::from(9)), None);
+    }
+}
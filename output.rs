@@ -0,0 +1,128 @@
+use crate::format::NumberFormat;
+use crate::uncertainty::Uncertain;
+use crate::value::Value;
+
+// Stage of solving an equation that produced an error.
+#[derive(Clone, Copy)]
+pub enum ErrorKind {
+    Tokenize,
+    Evaluate,
+    Variable,
+    Command,
+    Script,
+    Config,
+    Session,
+}
+
+impl ErrorKind {
+    fn name(&self) -> &str {
+        match self {
+            ErrorKind::Tokenize => "tokenize",
+            ErrorKind::Evaluate => "evaluate",
+            ErrorKind::Variable => "variable",
+            ErrorKind::Command => "command",
+            ErrorKind::Script => "script",
+            ErrorKind::Config => "config",
+            ErrorKind::Session => "session",
+        }
+    }
+}
+
+// Prints the result of an equation. Tokens and postfix expression are printed when revealed.
+// JSON results are always written at full precision.
+pub fn print_result(input: &str, result: &Value, revealed: Option<(&[String], &[String])>,
+                    format: &NumberFormat, json: bool) {
+    if json {
+        let mut object = format!("{{\"input\":{},\"result\":{}", json_string(input), json_value(result));
+        if let Some((tokens, expression)) = revealed {
+            object.push_str(&format!(",\"tokens\":{},\"postfix\":{}",
+                                     json_strings(tokens), json_strings(expression)));
+        }
+        println!("{}}}", object);
+        return;
+    }
+
+    if let Some((tokens, expression)) = revealed {
+        println!("Tokens: {:?}", tokens);
+        println!("Expression: {:?}", expression);
+    }
+    println!("Result: {}", format.format(result));
+}
+
+// Prints a changed variable value.
+pub fn print_variable(input: &str, variable: char, value: &Value, format: &NumberFormat, json: bool) {
+    if json {
+        println!("{{\"input\":{},\"variable\":{},\"result\":{}}}",
+                 json_string(input), json_string(&variable.to_string()), json_value(value));
+    } else {
+        println!("'{}' value changed to {}", variable, format.format(value));
+    }
+}
+
+// Prints text written by a command or a script print statement.
+pub fn print_output(text: &str, json: bool) {
+    if json {
+        println!("{{\"output\":{}}}", json_string(text));
+    } else {
+        println!("{}", text);
+    }
+}
+
+// Prints an error. span is the byte range of the failing equation within the input line, if it came from one.
+pub fn print_error(input: &str, kind: ErrorKind, message: &str, span: Option<(usize, usize)>, json: bool) {
+    if json {
+        let span = span.map_or(String::new(), |(start, end)| format!(",\"span\":[{},{}]", start, end));
+        println!("{{\"input\":{},\"error\":{{\"kind\":{},\"message\":{}{}}}}}",
+                 json_string(input), json_string(kind.name()), json_string(message), span);
+    } else {
+        println!("Error: {}", message);
+    }
+}
+
+// Formats a value as a JSON number, or array for lists. Non-finite floats become strings, as do dates
+// and durations, written in ISO 8601. Uncertain values become objects with their value and uncertainty,
+// or their bounds.
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Integer(integer) => integer.to_string(),
+        Value::Float(float) if float.is_finite() => float.to_string(),
+        Value::Float(float) => json_string(&float.to_string()),
+        Value::List(items) => {
+            let items: Vec<String> = items.iter().map(json_value).collect();
+            format!("[{}]", items.join(","))
+        },
+        Value::Date(date) => json_string(&date.to_string()),
+        Value::Duration(duration) => json_string(&duration.to_string()),
+        Value::Uncertain(Uncertain::Gaussian { value, error }) => {
+            format!("{{\"value\":{},\"uncertainty\":{}}}", json_value(&Value::Float(*value)),
+                    json_value(&Value::Float(*error)))
+        },
+        Value::Uncertain(Uncertain::Interval { low, high }) => {
+            format!("{{\"low\":{},\"high\":{}}}", json_value(&Value::Float(*low)), json_value(&Value::Float(*high)))
+        },
+    }
+}
+
+// Formats strings as a JSON array.
+fn json_strings(strings: &[String]) -> String {
+    let strings: Vec<String> = strings.iter().map(|string| json_string(string)).collect();
+    format!("[{}]", strings.join(","))
+}
+
+// Formats a string as a quoted and escaped JSON string.
+fn json_string(string: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
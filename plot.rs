@@ -0,0 +1,282 @@
+use crate::evaluate::evaluate;
+use crate::format::{Notation, NumberFormat};
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::series::{split_arguments, Substitution};
+use crate::tokenize::{tokenize_with_context, Context};
+use crate::value::Value;
+
+// Size of the chart in characters. Each character holds 2 by 4 braille dots, and x is sampled once
+// for each column of dots.
+const WIDTH: usize = 60;
+const HEIGHT: usize = 16;
+const COLUMNS: usize = WIDTH * 2;
+const ROWS: usize = HEIGHT * 4;
+
+// Bits of the braille dots in a character, by row and then column.
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+// Plots an equation of x from a start to an end, given as 'equation, start, end', as a chart of braille dots
+// with labeled axes. Points where the equation cannot be evaluated or is not finite are left out, and
+// points are not joined across jumps of more than half the chart, so discontinuities stay apart.
+pub fn plot(argument: &str, context: Context) -> Result<String, String> {
+    let separator = context.format.locale.argument_separator();
+    let [equation, start, end] = split_arguments(argument, separator)[..] else {
+        return Err(format!("Expected an equation of x, a start, and an end, as in /plot x^2{} -2{} 2",
+                           separator, separator));
+    };
+    let bound = |text: &str| {
+        let tokens = tokenize_with_context(text, context).ok()?;
+        evaluate(infix_to_postfix(&tokens)).and_then(|value| value.to_f64()).ok().filter(|bound| bound.is_finite())
+    };
+    let (start_text, end_text) = (start.trim(), end.trim());
+    let (start, end) = match (bound(start), bound(end)) {
+        (Some(start), Some(end)) if start < end && (end - start).is_finite() => (start, end),
+        _ => return Err("Plot start and end must be numbers with the start below the end".to_string()),
+    };
+
+    let mut substitution = Substitution::new(equation, 'x', context, 0)?;
+    let samples: Vec<Option<f64>> = (0..COLUMNS).map(|column| {
+        let x = start + (end - start) * column as f64 / (COLUMNS - 1) as f64;
+        substitution.evaluate(&Value::Float(x)).ok().filter(|y| y.is_finite())
+    }).collect();
+    let (low, high) = range(&samples).ok_or_else(|| {
+        format!("Could not evaluate {} at any point from {} to {}", equation.trim(), start_text, end_text)
+    })?;
+
+    let mut chart = Chart::new([start, end], [low, high]);
+    let mut previous = None;
+    for (column, sample) in samples.iter().enumerate() {
+        let row = sample.and_then(|y| chart.row(y));
+        if let Some(row) = row {
+            match previous {
+                Some(previous) if continuous(&samples, column, (ROWS - 1) as f64 / (high - low)) => {
+                    chart.line(column, previous, row)
+                },
+                _ => chart.dot(column, row),
+            }
+        }
+        previous = row;
+    }
+
+    let mut lines = vec![format!("y = {}", equation.trim())];
+    lines.extend(chart.render(context.format));
+    let missing = samples.iter().filter(|sample| sample.is_none()).count();
+    if missing > 0 {
+        lines.push(format!("Could not evaluate {} of {} points", missing, COLUMNS));
+    }
+    Ok(lines.join("\n"))
+}
+
+// Whether the samples of a column and the one before it are joined. A step counts as a jump, and is
+// left apart, when it is more than 2 rows and 4 times the steps on either side of it.
+fn continuous(samples: &[Option<f64>], column: usize, rows_per_unit: f64) -> bool {
+    let step = |column: usize| match (samples.get(column.wrapping_sub(1)), samples.get(column)) {
+        (Some(Some(a)), Some(Some(b))) => Some((b - a).abs()),
+        _ => None,
+    };
+    let Some(jump) = step(column) else { return false };
+    let neighbors = step(column.wrapping_sub(1)).into_iter().chain(step(column + 1)).fold(0.0, f64::max);
+    jump * rows_per_unit <= 2.0 || jump <= 4.0 * neighbors
+}
+
+// The range of y values shown, from the lowest to the highest sample. Samples far from the rest, as near
+// an asymptote, would flatten the chart, so when the samples span more than 20 times their middle 90%,
+// the range is that middle part with half of it added on each side.
+fn range(samples: &[Option<f64>]) -> Option<(f64, f64)> {
+    let mut values: Vec<f64> = samples.iter().flatten().copied().collect();
+    values.sort_by(f64::total_cmp);
+    let (low, high) = (*values.first()?, *values.last()?);
+    let (middle_low, middle_high) = (values[values.len() / 20], values[values.len() - 1 - values.len() / 20]);
+
+    let middle = middle_high - middle_low;
+    let (low, high) = if middle > 0.0 && high - low > 20.0 * middle {
+        ((middle_low - middle / 2.0).max(low), (middle_high + middle / 2.0).min(high))
+    } else {
+        (low, high)
+    };
+    if low == high {
+        return Some((low - 1.0, high + 1.0));
+    }
+    Some((low, high))
+}
+
+// Braille dots of a chart with columns of x from left to right and rows of y from top to bottom.
+struct Chart {
+    x: [f64; 2],
+    y: [f64; 2],
+    cells: [[u32; WIDTH]; HEIGHT],
+}
+
+impl Chart {
+    fn new(x: [f64; 2], y: [f64; 2]) -> Chart {
+        Chart { x, y, cells: [[0; WIDTH]; HEIGHT] }
+    }
+
+    // The row of dots of a y value, or None when it is outside the chart.
+    fn row(&self, y: f64) -> Option<usize> {
+        let [low, high] = self.y;
+        let row = ((high - y) / (high - low) * (ROWS - 1) as f64).round();
+        (0.0..ROWS as f64).contains(&row).then_some(row as usize)
+    }
+
+    // The column of dots of an x value.
+    fn column(&self, x: f64) -> usize {
+        let [start, end] = self.x;
+        ((x - start) / (end - start) * (COLUMNS - 1) as f64).round() as usize
+    }
+
+    fn dot(&mut self, column: usize, row: usize) {
+        self.cells[row / 4][column / 2] |= DOTS[row % 4][column % 2];
+    }
+
+    // Joins a dot to the one in the previous column with a vertical line in this column.
+    fn line(&mut self, column: usize, previous: usize, row: usize) {
+        for row in row.min(previous + 1)..=row.max(previous.saturating_sub(1)) {
+            self.dot(column, row);
+        }
+    }
+
+    // Writes the chart with y labels at the top, bottom, and 0, and x labels at the start, middle, and end.
+    // Lines are drawn for the axes where x and y are 0, in characters without dots.
+    fn render(&self, format: &NumberFormat) -> Vec<String> {
+        let format = NumberFormat { notation: Notation::Significant, digits: 4, grouping: false, ..*format };
+        let label = |value: f64| trim_zeros(format.format(&Value::Float(value)), format.locale.decimal_separator());
+        let [low, high] = self.y;
+        let [start, end] = self.x;
+        let x_axis = self.row(0.0).map(|row| row / 4);
+        let y_axis = (start..=end).contains(&0.0).then(|| self.column(0.0) / 2);
+
+        let mut labels = vec![None; HEIGHT];
+        labels[0] = Some(label(high));
+        labels[HEIGHT - 1] = Some(label(low));
+        if let Some(row) = x_axis.filter(|row| labels[*row].is_none()) {
+            labels[row] = Some(label(0.0));
+        }
+        let margin = labels.iter().flatten().map(|label| label.chars().count()).max().unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for (row, cells) in self.cells.iter().enumerate() {
+            let mut line = match &labels[row] {
+                Some(label) => format!("{:>margin$} ┤", label),
+                None => format!("{:margin$} │", ""),
+            };
+            for (column, bits) in cells.iter().enumerate() {
+                line.push(match (*bits, Some(row) == x_axis, Some(column) == y_axis) {
+                    (0, true, true) => '┼',
+                    (0, true, false) => '─',
+                    (0, false, true) => '│',
+                    (0, false, false) => ' ',
+                    (bits, _, _) => char::from_u32(0x2800 + bits).unwrap_or(' '),
+                });
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines.push(format!("{:margin$} └{}", "", "─".repeat(WIDTH)));
+
+        // The start label begins under the first column, the end label finishes under the last,
+        // and the middle label is centered when there is room for it.
+        let (start, middle, end) = (label(start), label((start + end) / 2.0), label(end));
+        let mut axis: Vec<char> = vec![' '; margin + 2 + WIDTH];
+        let mut place = |text: &str, position: usize| {
+            for (offset, c) in text.chars().enumerate() {
+                if let Some(cell) = axis.get_mut(position + offset) {
+                    *cell = c;
+                }
+            }
+        };
+        let end_position = (margin + 2 + WIDTH).saturating_sub(end.chars().count());
+        let middle_position = (margin + 2 + WIDTH / 2).saturating_sub(middle.chars().count() / 2);
+        if margin + 2 + start.chars().count() < middle_position
+            && middle_position + middle.chars().count() < end_position {
+            place(&middle, middle_position);
+        }
+        place(&start, margin + 2);
+        place(&end, end_position);
+        lines.push(axis.into_iter().collect::<String>().trim_end().to_string());
+        lines
+    }
+}
+
+// Removes the trailing zeros of a number's decimals, as in '3.000' or '1.500E8'.
+fn trim_zeros(number: String, decimal_separator: char) -> String {
+    let (digits, exponent) = number.split_at(number.find('E').unwrap_or(number.len()));
+    if !digits.contains(decimal_separator) {
+        return number;
+    }
+    format!("{}{}", digits.trim_end_matches('0').trim_end_matches(decimal_separator), exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uncertainty::Propagation;
+    use std::collections::HashMap;
+
+    fn plot_equation(argument: &str) -> Result<String, String> {
+        let variables = HashMap::from([('p', std::f64::consts::PI.to_string())]);
+        let context = Context {
+            variables: &variables,
+            functions: &HashMap::new(),
+            format: &NumberFormat::default(),
+            results: &[],
+            uncertainty: Propagation::Gaussian,
+        };
+        plot(argument, context)
+    }
+
+    #[test]
+    fn plots_have_axes_and_labels() {
+        let chart = plot_equation("x^2 - 1, -2, 2").unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines[0], "y = x^2 - 1");
+        assert!(lines[1].starts_with("      3 ┤"));
+        assert!(lines[HEIGHT].starts_with("-0.9997 ┤"));
+        assert!(lines.iter().any(|line| line.starts_with("      0 ┤─")));
+        assert_eq!(lines[HEIGHT + 1], format!("        └{}", "─".repeat(WIDTH)));
+        assert_eq!(lines[HEIGHT + 2].split_whitespace().collect::<Vec<&str>>(), ["-2", "0", "2"]);
+        assert!(chart.contains('┼'));
+        assert!(!chart.contains("Could not evaluate"));
+    }
+
+    #[test]
+    fn implicit_multiplication_plots_like_explicit() {
+        for (implicit, explicit) in [("x(x+1)", "x*(x+1)"), ("2x", "2*x"), ("xx", "x*x"), ("x2", "x*2")] {
+            let chart = plot_equation(&format!("{}, -2, 2", implicit)).unwrap();
+            let expected = plot_equation(&format!("{}, -2, 2", explicit)).unwrap();
+            assert_eq!(chart.split_once('\n').unwrap().1, expected.split_once('\n').unwrap().1, "{}", implicit);
+            assert!(!chart.contains("Could not evaluate"));
+        }
+    }
+
+    #[test]
+    fn points_that_cannot_be_evaluated_are_left_out() {
+        let chart = plot_equation("1/(x - 0.5) + R(x), -1, 1").unwrap();
+        assert!(chart.ends_with(&format!("Could not evaluate {} of {} points", COLUMNS / 2, COLUMNS)));
+        assert!(plot_equation("R(x), -2, -1").is_err());
+        assert!(plot_equation("x, 2, 1").is_err());
+        assert!(plot_equation("x, 1").is_err());
+        assert!(plot_equation("x, -1E308, 1E308").is_err());
+    }
+
+    #[test]
+    fn dots_are_joined_and_outliers_are_left_out_of_the_range() {
+        let mut chart = Chart::new([0.0, 1.0], [0.0, 1.0]);
+        chart.line(1, 0, 3);
+        assert_eq!(chart.cells[0][0], DOTS[1][1] | DOTS[2][1] | DOTS[3][1]);
+        assert_eq!(range(&[Some(1.0), None, Some(1.0)]), Some((0.0, 2.0)));
+
+        assert_eq!(trim_zeros("1.500E8".to_string(), '.'), "1.5E8");
+        assert_eq!(trim_zeros("100".to_string(), '.'), "100");
+        assert_eq!(trim_zeros("-2,000".to_string(), ','), "-2");
+
+        let sawtooth = [Some(0.7), Some(0.8), Some(0.9), Some(0.0), Some(0.1), None, Some(0.2)];
+        assert!(continuous(&sawtooth, 2, 63.0));
+        assert!(!continuous(&sawtooth, 3, 63.0));
+        assert!(continuous(&sawtooth, 4, 63.0));
+        assert!(!continuous(&sawtooth, 6, 63.0));
+        let mut samples: Vec<Option<f64>> = (0..100).map(|i| Some(i as f64)).collect();
+        samples.push(Some(1E9));
+        let (_, high) = range(&samples).unwrap();
+        assert!(high < 200.0);
+    }
+}
@@ -0,0 +1,449 @@
+use crate::complex_evaluate::separate_vector;
+use crate::evaluate::evaluate;
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::value::Value;
+use num_bigint::BigInt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use statrs::distribution::{Binomial, Continuous, ContinuousCDF, Normal, Poisson, StudentsT};
+use std::cell::RefCell;
+use std::f64::consts::PI;
+
+// Most probabilities summed for one binomial or Poisson cumulative probability.
+const MAXIMUM_TERMS: u32 = 1_000_000;
+
+thread_local! {
+    // Random number generator shared by every draw, seeded from the system unless a seed is set.
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_os_rng());
+}
+
+// Seeds the random number generator so draws can be repeated, or seeds it from the system with None.
+pub fn seed(seed: Option<u64>) {
+    let rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    RNG.with(|cell| *cell.borrow_mut() = rng);
+}
+
+// Evaluates comma separated numbers.
+fn numbers(tokens: &Vec<String>) -> Result<Vec<f64>, &'static str> {
+    separate_vector(tokens, 0).iter()
+        .map(|equation_tokens| evaluate(infix_to_postfix(equation_tokens)).and_then(|value| value.to_f64()))
+        .collect::<Result<Vec<f64>, String>>()
+        .map_err(|_| "Could not evaluate distribution value")
+}
+
+// Random draws are written as tokens naming the distribution, like 'randn[0, 1]', and drawn each time
+// they are evaluated, so each term of 'S[1, 1000, rand[]]' gets its own draw.
+
+// Returns a uniform draw between low and high as [low, high], or between 0 and 1 as [].
+pub fn uniform(tokens: &Vec<String>) -> Result<String, &str> {
+    let [low, high] = match numbers(tokens)?[..] {
+        [] => [0.0, 1.0],
+        [low, high] => [low, high],
+        _ => return Err("Expected rand[] or rand[low, high]"),
+    };
+    if low >= high || !(high - low).is_finite() {
+        return Err("rand needs a finite low bound below the high bound");
+    }
+    Ok(format!("rand[{}, {}]", low, high))
+}
+
+// Returns a normal draw as [mean, standard deviation], or a standard normal draw as [].
+pub fn normal(tokens: &Vec<String>) -> Result<String, &str> {
+    let [mean, deviation] = match numbers(tokens)?[..] {
+        [] => [0.0, 1.0],
+        [mean, deviation] => [mean, deviation],
+        _ => return Err("Expected randn[] or randn[mean, standard deviation]"),
+    };
+    Normal::new(mean, deviation).map_err(|_| "randn needs a finite mean and a positive standard deviation")?;
+    Ok(format!("randn[{}, {}]", mean, deviation))
+}
+
+// Returns a draw of a whole number from low to high, inclusive, as [low, high].
+pub fn integer(tokens: &Vec<String>) -> Result<String, &str> {
+    let [low, high] = numbers(tokens)?[..] else { return Err("Expected randint[low, high]") };
+    if low.fract() != 0.0 || high.fract() != 0.0 || low.abs() >= i64::MAX as f64 || high.abs() >= i64::MAX as f64 {
+        return Err("randint bounds must be whole numbers");
+    }
+    if low > high {
+        return Err("randint needs a low bound no greater than the high bound");
+    }
+    Ok(format!("randint[{}, {}]", low as i64, high as i64))
+}
+
+// Draws a value for a random draw token, or returns None for any other token.
+pub fn sample(token: &str) -> Option<Value> {
+    let (name, arguments) = token.strip_suffix(']')?.split_once('[')?;
+    let (a, b) = arguments.split_once(", ")?;
+    RNG.with(|cell| {
+        let mut rng = cell.borrow_mut();
+        let value = match name {
+            "rand" => {
+                let (low, high) = (a.parse::<f64>().ok()?, b.parse::<f64>().ok()?);
+                Value::Float(low + (high - low) * rng.random::<f64>())
+            },
+            "randn" => {
+                let normal = Normal::new(a.parse().ok()?, b.parse().ok()?).ok()?;
+                // Values of exactly 0 have no inverse, so draws are taken from (0, 1).
+                let probability = 1.0 - rng.random::<f64>();
+                Value::Float(normal.inverse_cdf(probability))
+            },
+            "randint" => {
+                let (low, high) = (a.parse::<i64>().ok()?, b.parse::<i64>().ok()?);
+                Value::Integer(BigInt::from(rng.random_range(low..=high)))
+            },
+            _ => return None,
+        };
+        Some(value)
+    })
+}
+
+// Checks that a probability is strictly between 0 and 1 for an inverse distribution function.
+fn probability(probability: f64) -> Result<f64, &'static str> {
+    if probability > 0.0 && probability < 1.0 {
+        Ok(probability)
+    } else {
+        Err("Inverse distribution functions need a probability between 0 and 1")
+    }
+}
+
+// Converts a count for a discrete distribution, which must be a whole number of at least 0.
+fn count(count: f64) -> Result<u64, &'static str> {
+    if count >= 0.0 && count.fract() == 0.0 && count < u64::MAX as f64 {
+        Ok(count as u64)
+    } else {
+        Err("Counts for binomial and Poisson distributions must be whole numbers of at least 0")
+    }
+}
+
+// Reads [value, mean, standard deviation] for a normal distribution function. The mean and standard
+// deviation default to 0 and 1.
+fn normal_arguments(tokens: &Vec<String>, usage: &'static str) -> Result<(f64, Normal), &'static str> {
+    let (x, mean, deviation) = match numbers(tokens)?[..] {
+        [x] => (x, 0.0, 1.0),
+        [x, mean, deviation] => (x, mean, deviation),
+        _ => return Err(usage),
+    };
+    let normal = Normal::new(mean, deviation).map_err(|_| "Normal distributions need a positive standard deviation")?;
+    Ok((x, normal))
+}
+
+// Reads [value, degrees of freedom] for a Student's t distribution function.
+fn t_arguments(tokens: &Vec<String>, usage: &'static str) -> Result<(f64, StudentsT), &'static str> {
+    let [x, freedom] = numbers(tokens)?[..] else { return Err(usage) };
+    let t = StudentsT::new(0.0, 1.0, freedom).map_err(|_| "t distributions need positive degrees of freedom")?;
+    Ok((x, t))
+}
+
+// Reads [value, trials, success probability] for a binomial distribution function.
+fn binomial_arguments(tokens: &Vec<String>, usage: &'static str) -> Result<(f64, Binomial), &'static str> {
+    let [x, trials, success] = numbers(tokens)?[..] else { return Err(usage) };
+    let binomial = Binomial::new(success, count(trials)?)
+        .map_err(|_| "Binomial distributions need a success probability from 0 to 1")?;
+    Ok((x, binomial))
+}
+
+// Reads [value, mean] for a Poisson distribution function.
+fn poisson_arguments(tokens: &Vec<String>, usage: &'static str) -> Result<(f64, Poisson), &'static str> {
+    let [x, mean] = numbers(tokens)?[..] else { return Err(usage) };
+    let poisson = Poisson::new(mean).map_err(|_| "Poisson distributions need a positive mean")?;
+    Ok((x, poisson))
+}
+
+// Computes the normal probability density as [x, mean, standard deviation].
+pub fn normal_pdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (x, normal) = normal_arguments(tokens, "Expected normpdf[x, mean, standard deviation]")?;
+    Ok(normal.pdf(x).to_string())
+}
+
+// Computes the normal cumulative probability as [x, mean, standard deviation].
+pub fn normal_cdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (x, normal) = normal_arguments(tokens, "Expected normcdf[x, mean, standard deviation]")?;
+    Ok(normal.cdf(x).to_string())
+}
+
+// Computes the value with a normal cumulative probability as [probability, mean, standard deviation].
+pub fn normal_inverse(tokens: &Vec<String>) -> Result<String, &str> {
+    let (p, normal) = normal_arguments(tokens, "Expected norminv[probability, mean, standard deviation]")?;
+    Ok(normal.inverse_cdf(probability(p)?).to_string())
+}
+
+// Computes the Student's t probability density as [x, degrees of freedom].
+pub fn t_pdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (x, t) = t_arguments(tokens, "Expected tpdf[x, degrees of freedom]")?;
+    Ok(t.pdf(x).to_string())
+}
+
+// Computes the Student's t cumulative probability as [x, degrees of freedom].
+pub fn t_cdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (x, t) = t_arguments(tokens, "Expected tcdf[x, degrees of freedom]")?;
+    Ok(t.cdf(x).to_string())
+}
+
+// Computes the value with a Student's t cumulative probability as [probability, degrees of freedom].
+pub fn t_inverse(tokens: &Vec<String>) -> Result<String, &str> {
+    let (p, t) = t_arguments(tokens, "Expected tinv[probability, degrees of freedom]")?;
+    Ok(t.inverse_cdf(probability(p)?).to_string())
+}
+
+// Stirling's series error ln(n!) - ln(sqrt(2πn) (n/e)^n), which keeps large factorials from losing digits.
+fn stirling_error(n: f64) -> f64 {
+    let squared = n * n;
+    match n {
+        0.0 => 0.0,
+        n if n <= 15.0 => {
+            (2..=n as u64).map(|i| (i as f64).ln()).sum::<f64>() - (n + 0.5) * n.ln() + n - (2.0 * PI).sqrt().ln()
+        }
+        n if n > 500.0 => (1.0 / 12.0 - 1.0 / 360.0 / squared) / n,
+        n if n > 80.0 => (1.0 / 12.0 - (1.0 / 360.0 - 1.0 / 1260.0 / squared) / squared) / n,
+        n if n > 35.0 => (1.0 / 12.0 - (1.0 / 360.0 - (1.0 / 1260.0 - 1.0 / 1680.0 / squared) / squared) / squared) / n,
+        n => {
+            (1.0 / 12.0
+                - (1.0 / 360.0 - (1.0 / 1260.0 - (1.0 / 1680.0 - 1.0 / 1188.0 / squared) / squared) / squared)
+                    / squared)
+                / n
+        }
+    }
+}
+
+// Computes the deviance x ln(x / mean) + mean - x, summing a series when x is near the mean where the direct form
+// cancels.
+fn deviance(x: f64, mean: f64) -> f64 {
+    if (x - mean).abs() >= 0.1 * (x + mean) {
+        return x * (x / mean).ln() + mean - x;
+    }
+    let ratio = (x - mean) / (x + mean);
+    let (mut sum, mut term) = ((x - mean) * ratio, 2.0 * x * ratio);
+    for j in 1.. {
+        term *= ratio * ratio;
+        let next = sum + term / (2 * j + 1) as f64;
+        if next == sum {
+            break;
+        }
+        sum = next;
+    }
+    sum
+}
+
+// Computes the binomial probability of k successes with Loader's saddle point form, which stays accurate for
+// billions of trials.
+fn binomial_probability(k: u64, binomial: &Binomial) -> f64 {
+    let (n, p) = (binomial.n(), binomial.p());
+    let (x, trials, q) = (k as f64, n as f64, 1.0 - p);
+    match k {
+        k if k > n => 0.0,
+        _ if p == 0.0 => (k == 0) as u8 as f64,
+        _ if q == 0.0 => (k == n) as u8 as f64,
+        0 => (trials * (-p).ln_1p()).exp(),
+        k if k == n => (trials * p.ln()).exp(),
+        _ => {
+            let exponent = stirling_error(trials)
+                - stirling_error(x)
+                - stirling_error(trials - x)
+                - deviance(x, trials * p)
+                - deviance(trials - x, trials * q);
+            (exponent - 0.5 * ((2.0 * PI).ln() + x.ln() + (-x / trials).ln_1p())).exp()
+        }
+    }
+}
+
+// Computes the Poisson probability of k events with Loader's saddle point form.
+fn poisson_probability(k: u64, poisson: &Poisson) -> f64 {
+    let (x, mean) = (k as f64, poisson.lambda());
+    match k {
+        0 => (-mean).exp(),
+        _ => (-stirling_error(x) - deviance(x, mean)).exp() / (2.0 * PI * x).sqrt(),
+    }
+}
+
+// Sums probabilities from count `start` away from the mean, where `step` gives the next count and the ratio of its
+// probability to the current one. Ratios shrink away from the mean, so the sum stops once a geometric bound on the
+// remaining terms cannot change it.
+fn tail(probability: f64, start: u64, step: impl Fn(u64) -> Option<(u64, f64)>) -> Result<f64, &'static str> {
+    let (mut sum, mut term, mut count) = (0.0, probability, start);
+    for _ in 0..MAXIMUM_TERMS {
+        sum += term;
+        let Some((next, ratio)) = step(count) else { return Ok(sum) };
+        term *= ratio;
+        if term == 0.0 || (ratio < 1.0 && term / (1.0 - ratio) <= sum * f64::EPSILON) {
+            return Ok(sum);
+        }
+        count = next;
+    }
+    Err("Binomial and Poisson distributions this spread out take too long to compute")
+}
+
+// Computes the binomial probability of at most k successes, summing whichever tail lies away from the mean.
+fn binomial_cumulative(k: u64, binomial: &Binomial) -> Result<f64, &'static str> {
+    let (n, p) = (binomial.n(), binomial.p());
+    let odds = p / (1.0 - p);
+    if k >= n {
+        Ok(1.0)
+    } else if (k as f64) < n as f64 * p {
+        let down = |j: u64| (j > 0).then(|| (j - 1, j as f64 / (n - j + 1) as f64 / odds));
+        tail(binomial_probability(k, binomial), k, down)
+    } else {
+        let up = |j: u64| (j < n).then(|| (j + 1, (n - j) as f64 / (j + 1) as f64 * odds));
+        Ok(1.0 - tail(binomial_probability(k + 1, binomial), k + 1, up)?)
+    }
+}
+
+// Computes the Poisson probability of at most k events, summing whichever tail lies away from the mean.
+fn poisson_cumulative(k: u64, poisson: &Poisson) -> Result<f64, &'static str> {
+    let mean = poisson.lambda();
+    if (k as f64) < mean {
+        let down = |j: u64| (j > 0).then(|| (j - 1, j as f64 / mean));
+        tail(poisson_probability(k, poisson), k, down)
+    } else {
+        let up = |j: u64| (j < u64::MAX).then(|| (j + 1, mean / (j + 1) as f64));
+        Ok(1.0 - tail(poisson_probability(k + 1, poisson), k + 1, up)?)
+    }
+}
+
+// Finds the fewest counts whose cumulative probability reaches p. A bracket grows from the mean in steps that double
+// from the standard deviation, then halves until the counts on either side of p are adjacent.
+fn quantile(
+    p: f64,
+    mean: f64,
+    deviation: f64,
+    cumulative: impl Fn(u64) -> Result<f64, &'static str>,
+) -> Result<u64, &'static str> {
+    let mut step = deviation.max(1.0) as u64;
+    let (mut low, mut high) = (mean as u64, mean as u64);
+    if cumulative(high)? >= p {
+        loop {
+            if high == 0 {
+                return Ok(0);
+            }
+            low = high.saturating_sub(step);
+            if cumulative(low)? < p {
+                break;
+            }
+            (high, step) = (low, step.saturating_mul(2));
+        }
+    } else {
+        loop {
+            high = low.checked_add(step).ok_or("Inverse distribution functions need a smaller probability")?;
+            if cumulative(high)? >= p {
+                break;
+            }
+            (low, step) = (high, step.saturating_mul(2));
+        }
+    }
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if cumulative(middle)? >= p {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+    Ok(high)
+}
+
+// Computes the binomial probability of k successes as [k, trials, success probability].
+pub fn binomial_pdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (k, binomial) = binomial_arguments(tokens, "Expected binompdf[k, trials, success probability]")?;
+    Ok(binomial_probability(count(k)?, &binomial).to_string())
+}
+
+// Computes the binomial probability of at most k successes as [k, trials, success probability].
+pub fn binomial_cdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (k, binomial) = binomial_arguments(tokens, "Expected binomcdf[k, trials, success probability]")?;
+    Ok(binomial_cumulative(count(k)?, &binomial)?.to_string())
+}
+
+// Computes the fewest successes with at least a cumulative probability as
+// [probability, trials, success probability].
+pub fn binomial_inverse(tokens: &Vec<String>) -> Result<String, &str> {
+    let (p, binomial) = binomial_arguments(tokens, "Expected binominv[probability, trials, success probability]")?;
+    let mean = binomial.n() as f64 * binomial.p();
+    let deviation = (mean * (1.0 - binomial.p())).sqrt();
+    Ok(quantile(probability(p)?, mean, deviation, |k| binomial_cumulative(k, &binomial))?.to_string())
+}
+
+// Computes the Poisson probability of k events as [k, mean].
+pub fn poisson_pdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (k, poisson) = poisson_arguments(tokens, "Expected poissonpdf[k, mean]")?;
+    Ok(poisson_probability(count(k)?, &poisson).to_string())
+}
+
+// Computes the Poisson probability of at most k events as [k, mean].
+pub fn poisson_cdf(tokens: &Vec<String>) -> Result<String, &str> {
+    let (k, poisson) = poisson_arguments(tokens, "Expected poissoncdf[k, mean]")?;
+    Ok(poisson_cumulative(count(k)?, &poisson)?.to_string())
+}
+
+// Computes the fewest events with at least a cumulative probability as [probability, mean].
+pub fn poisson_inverse(tokens: &Vec<String>) -> Result<String, &str> {
+    let (p, poisson) = poisson_arguments(tokens, "Expected poissoninv[probability, mean]")?;
+    let mean = poisson.lambda();
+    Ok(quantile(probability(p)?, mean, mean.sqrt(), |k| poisson_cumulative(k, &poisson))?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(text: &str) -> Vec<String> {
+        text.split(' ').map(str::to_string).collect()
+    }
+
+    fn close(result: Result<String, &str>, expected: f64) -> bool {
+        result.is_ok_and(|result| (result.parse::<f64>().unwrap() - expected).abs() < 1E-9)
+    }
+
+    #[test]
+    fn draws_are_repeatable_with_a_seed() {
+        let draws = || {
+            seed(Some(7));
+            ["rand[2, 3]", "randn[0, 1]", "randint[1, 6]"].map(|token| sample(token).unwrap())
+        };
+        let first = draws();
+        assert_eq!(first, draws());
+        assert!(matches!(first[0], Value::Float(value) if (2.0..3.0).contains(&value)));
+        assert!(matches!(&first[2], Value::Integer(value) if (BigInt::from(1)..=BigInt::from(6)).contains(value)));
+        assert_eq!(sample("randint[4, 4]"), Some(Value::Integer(BigInt::from(4))));
+        assert_eq!(sample("4"), None);
+    }
+
+    #[test]
+    fn draws_are_validated() {
+        assert_eq!(uniform(&Vec::new()), Ok("rand[0, 1]".to_string()));
+        assert_eq!(integer(&arguments("1 , 6")), Ok("randint[1, 6]".to_string()));
+        assert!(integer(&arguments("1.5 , 6")).is_err());
+        assert!(integer(&arguments("6 , 1")).is_err());
+        assert!(normal(&arguments("0 , ~1")).is_err());
+        assert!(uniform(&arguments("1")).is_err());
+    }
+
+    #[test]
+    fn computes_distribution_functions() {
+        assert!(close(normal_cdf(&arguments("1.96")), 0.9750021048517795));
+        assert!(close(normal_inverse(&arguments("0.975 , 100 , 15")), 129.3994597681008));
+        assert!(close(normal_pdf(&arguments("0")), 0.3989422804014327));
+        assert!(close(t_inverse(&arguments("0.975 , 10")), 2.2281388519649385));
+        assert!(close(t_cdf(&arguments("0 , 3")), 0.5));
+        assert!(close(binomial_pdf(&arguments("3 , 10 , 0.5")), 0.1171875));
+        assert_eq!(binomial_inverse(&arguments("0.5 , 10 , 0.5")), Ok("5".to_string()));
+        assert!(close(poisson_cdf(&arguments("2 , 3")), 0.42319008112684353));
+        assert_eq!(poisson_inverse(&arguments("0.5 , 3")), Ok("3".to_string()));
+        assert!(binomial_pdf(&arguments("1.5 , 10 , 0.5")).is_err());
+        assert!(normal_inverse(&arguments("1")).is_err());
+    }
+
+    #[test]
+    fn large_distributions_keep_their_accuracy() {
+        assert_eq!(poisson_inverse(&arguments("0.999999999999 , 1000000")), Ok("1007043".to_string()));
+        assert!(close(poisson_cdf(&arguments("1000000 , 1000000")), 0.5002659614862837));
+        assert_eq!(binomial_inverse(&arguments("0.5 , 1000000 , 0.5")), Ok("500000".to_string()));
+        assert!(close(binomial_cdf(&arguments("499999 , 1000000 , 0.5")), 0.4996010578193341));
+        assert!(close(binomial_cdf(&arguments("500000 , 1000000 , 0.5")), 0.5003989421806659));
+        assert!(close(binomial_cdf(&arguments("3000000 , 10000000 , 0.3")), 0.5001560012458526));
+        assert_eq!(binomial_inverse(&arguments("0.5001 , 10000000 , 0.3")), Ok("3000000".to_string()));
+        let cumulative = |k: u64| binomial_cdf(&arguments(&format!("{k} , 1000000 , 0.5"))).unwrap().parse::<f64>();
+        assert!((499990..500010).all(|k| cumulative(k).unwrap() < cumulative(k + 1).unwrap()));
+    }
+}
@@ -0,0 +1,391 @@
+use crate::command::{change_variable, check_mutable, print_command, run_command};
+use crate::evaluate::evaluate;
+use crate::function::parse_definition;
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::output::{self, ErrorKind};
+use crate::session::{Calculation, Session};
+use crate::tokenize::tokenize_with_context;
+use crate::undo;
+use crate::value::Value;
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::fs;
+use std::path::Path;
+
+// Words that begin script statements and cannot be used as function names.
+pub const KEYWORDS: [&str; 8] = ["let", "def", "if", "else", "end", "while", "for", "print"];
+
+// Maximum number of iterations of a single while or for loop.
+const MAX_LOOP_ITERATIONS: usize = 1_000_000;
+
+// A statement and the line number it starts on.
+struct Line {
+    number: usize,
+    statement: Statement,
+}
+
+// A script statement. Equations are stored as text and solved each time they are reached.
+// Values of for and print are split when reached, since the locale decides their separator.
+enum Statement {
+    Let(char, String),
+    Def(String),
+    If(String, Vec<Line>, Vec<Line>),
+    While(String, Vec<Line>),
+    For(char, String, Vec<Line>),
+    Print(String),
+    Input(String),
+}
+
+// Reads and runs a script file.
+pub fn run_file(path: &Path, session: &mut Session) -> Result<(), String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    run_script(&source, session).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Parses a whole script before running any of it, so syntax errors never leave it half run.
+pub fn run_script(source: &str, session: &mut Session) -> Result<(), String> {
+    let lines: Vec<(usize, &str)> = source.lines().enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let mut next = 0;
+    let (statements, _) = parse_block(&lines, &mut next, &[])?;
+    execute_block(&statements, session)
+}
+
+// Solves or executes each ';' separated equation in a line like interactive input.
+// Returns false if any of them failed.
+pub fn run_line(text: &str, session: &mut Session) -> bool {
+    let mut success = true;
+    let mut start = 0;
+    for equation in split_equations(text, session.settings.format.locale.argument_separator() == ';') {
+        let trimmed_eq = equation.trim();
+        let offset = start + equation.len() - equation.trim_start().len();
+        let span = (offset, offset + trimmed_eq.len());
+        start += equation.len() + 1;
+
+        let json = session.settings.json();
+        if let Some(first) = trimmed_eq.chars().next() {
+            success &= match first {
+                '/' => {
+                    let result = if undo::is_undo_command(trimmed_eq) {
+                        run_command(trimmed_eq, session)
+                    } else {
+                        undo::record(trimmed_eq, session, |session| run_command(trimmed_eq, session))
+                    };
+                    print_command(trimmed_eq, result, span, session)
+                },
+                '_' => {
+                    match undo::record(trimmed_eq, session, |session| change_variable(trimmed_eq, session)) {
+                        Ok((variable, value)) => {
+                            output::print_variable(trimmed_eq, variable, &value, &session.settings.format,
+                                                   json);
+                            true
+                        },
+                        Err(e) => {
+                            output::print_error(trimmed_eq, ErrorKind::Variable, &e, Some(span), json);
+                            false
+                        },
+                    }
+                },
+                _ => {
+                    match tokenize_with_context(trimmed_eq, session.context()) {
+                        Ok(tokens) => {
+                            let expression = infix_to_postfix(&tokens);
+                            let revealed = session.settings.reveal
+                                .then_some((tokens.as_slice(), expression.as_slice()));
+
+                            match evaluate(expression.clone()) {
+                                Ok(result) => {
+                                    output::print_result(trimmed_eq, &result, revealed,
+                                                         &session.settings.format, json);
+                                    session.variables.insert('=', result.to_string());
+                                    session.results.push(Calculation {
+                                        input: trimmed_eq.to_string(),
+                                        result: result.to_string(),
+                                    });
+                                    true
+                                },
+                                Err(e) => {
+                                    output::print_error(trimmed_eq, ErrorKind::Evaluate, &e, Some(span), json);
+                                    false
+                                },
+                            }
+                        },
+                        Err(e) => {
+                            output::print_error(trimmed_eq, ErrorKind::Tokenize, &e, Some(span), json);
+                            false
+                        },
+                    }
+                }
+            };
+        }
+    }
+    success
+}
+
+// Splits a line into ';' separated equations. When ';' also separates complex operator values,
+// only those outside of brackets separate equations, and a command takes the rest of the line,
+// since its arguments may be separated by ';' too, as in '/plot x^2; -2; 2'.
+fn split_equations(text: &str, within_brackets: bool) -> Vec<&str> {
+    let mut equations = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, c) in text.char_indices() {
+        match c {
+            '[' if within_brackets => depth += 1,
+            ']' if within_brackets => depth -= 1,
+            ';' if within_brackets && text[start..].trim_start().starts_with('/') => {},
+            ';' if depth <= 0 => {
+                equations.push(&text[start..position]);
+                start = position + 1;
+            },
+            _ => {},
+        }
+    }
+    equations.push(&text[start..]);
+    equations
+}
+
+// Parses statements until a line starting with one of terminators, which is returned.
+// Returns no terminator at the end of the script.
+fn parse_block<'a>(lines: &[(usize, &'a str)], next: &mut usize,
+                   terminators: &[&str]) -> Result<(Vec<Line>, Option<&'a str>), String> {
+    let mut block = Vec::new();
+    while let Some(&(number, text)) = lines.get(*next) {
+        *next += 1;
+        let (keyword, rest) = match text.split_once(char::is_whitespace) {
+            Some((keyword, rest)) => (keyword, rest.trim()),
+            None => (text, ""),
+        };
+        if terminators.contains(&keyword) && rest.is_empty() {
+            return Ok((block, Some(keyword)));
+        }
+        let error = |message: String| format!("Line {}: {}", number, message);
+
+        let statement = match keyword {
+            "let" => {
+                let (variable, equation) = parse_assignment(rest).map_err(error)?;
+                Statement::Let(variable, equation.to_string())
+            },
+            "def" => {
+                parse_definition(rest).map_err(error)?;
+                Statement::Def(rest.to_string())
+            },
+            "if" | "while" if rest.is_empty() => return Err(error(format!("Missing condition after {}", keyword))),
+            "if" => {
+                let (then_block, terminator) = parse_block(lines, next, &["else", "end"])?;
+                let else_block = match terminator {
+                    Some("else") => parse_closed_block(lines, next, number, keyword)?,
+                    Some(_) => Vec::new(),
+                    None => return Err(error("Missing end for if".to_string())),
+                };
+                Statement::If(rest.to_string(), then_block, else_block)
+            },
+            "while" => Statement::While(rest.to_string(), parse_closed_block(lines, next, number, keyword)?),
+            "for" => {
+                let (variable, range) = parse_assignment(rest).map_err(error)?;
+                Statement::For(variable, range.to_string(), parse_closed_block(lines, next, number, keyword)?)
+            },
+            "print" => Statement::Print(rest.to_string()),
+            "else" | "end" => return Err(error(format!("Unexpected {}", text))),
+            _ => Statement::Input(text.to_string()),
+        };
+        block.push(Line { number, statement });
+    }
+    Ok((block, None))
+}
+
+// Parses the body of a block statement that must be closed by end.
+fn parse_closed_block(lines: &[(usize, &str)], next: &mut usize,
+                      number: usize, keyword: &str) -> Result<Vec<Line>, String> {
+    match parse_block(lines, next, &["end"])? {
+        (block, Some(_)) => Ok(block),
+        (_, None) => Err(format!("Line {}: Missing end for {}", number, keyword)),
+    }
+}
+
+// Parses 'variable = equation' into a mutable variable and its equation.
+fn parse_assignment(text: &str) -> Result<(char, &str), String> {
+    let (variable, equation) = text.split_once('=')
+        .ok_or_else(|| format!("Expected variable = equation but found {}", text))?;
+    let mut chars = variable.trim().chars();
+    let variable = match (chars.next(), chars.next()) {
+        (Some(variable), None) => variable,
+        _ => return Err(format!("{} is not a valid variable", variable.trim())),
+    };
+    check_mutable(variable)?;
+    Ok((variable, equation.trim()))
+}
+
+// Splits text on separator outside of brackets and quotes.
+fn split_arguments(text: &str, separator: char) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    for (position, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' if !quoted => depth += 1,
+            ']' if !quoted => depth -= 1,
+            _ if c == separator && !quoted && depth == 0 => {
+                arguments.push(text[start..position].trim().to_string());
+                start = position + 1;
+            },
+            _ => {},
+        }
+    }
+    if !text[start..].trim().is_empty() || !arguments.is_empty() {
+        arguments.push(text[start..].trim().to_string());
+    }
+    arguments
+}
+
+// Runs statements in order, stopping at the first error.
+fn execute_block(block: &[Line], session: &mut Session) -> Result<(), String> {
+    for line in block {
+        let error = |message: String| format!("Line {}: {}", line.number, message);
+        match &line.statement {
+            Statement::Let(variable, equation) => {
+                undo::record(&format!("let {} = {}", variable, equation), session, |session| {
+                    let value = solve(equation, session).map_err(error)?;
+                    session.variables.insert(*variable, value.to_string());
+                    Ok::<(), String>(())
+                })?;
+            },
+            Statement::Def(definition) => {
+                let (name, function) = parse_definition(definition).map_err(error)?;
+                let input = format!("def {}", definition);
+                undo::record(&input, session, |session| session.functions.insert(name, function));
+            },
+            Statement::If(condition, then_block, else_block) => {
+                if solve(condition, session).and_then(|value| value.is_true()).map_err(error)? {
+                    execute_block(then_block, session)?;
+                } else {
+                    execute_block(else_block, session)?;
+                }
+            },
+            Statement::While(condition, body) => {
+                let mut iterations = 0;
+                while solve(condition, session).and_then(|value| value.is_true()).map_err(error)? {
+                    iterations += 1;
+                    if iterations > MAX_LOOP_ITERATIONS {
+                        return Err(error(format!("Loop exceeded {} iterations", MAX_LOOP_ITERATIONS)));
+                    }
+                    execute_block(body, session)?;
+                }
+            },
+            Statement::For(variable, range, body) => {
+                undo::record(&format!("for {} = {}", variable, range), session, |session| {
+                    let separator = session.settings.format.locale.argument_separator();
+                    let range = split_arguments(range, separator);
+                    if !(2..=3).contains(&range.len()) {
+                        let message = format!("Expected for variable = start{} end{} step", separator, separator);
+                        return Err(error(message));
+                    }
+                    let range = range.iter()
+                        .map(|equation| solve(equation, session))
+                        .collect::<Result<Vec<Value>, String>>()
+                        .map_err(error)?;
+                    for value in loop_values(&range).map_err(error)? {
+                        session.variables.insert(*variable, value.to_string());
+                        execute_block(body, session)?;
+                    }
+                    Ok(())
+                })?;
+            },
+            Statement::Print(items) => {
+                let mut text = Vec::new();
+                for item in split_arguments(items, session.settings.format.locale.argument_separator()) {
+                    match item.strip_prefix('"').and_then(|item| item.strip_suffix('"')) {
+                        Some(string) => text.push(string.to_string()),
+                        None => text.push(session.settings.format.format(&solve(&item, session).map_err(error)?)),
+                    }
+                }
+                output::print_output(&text.join(" "), session.settings.json());
+            },
+            Statement::Input(text) => {
+                if !run_line(text, session) {
+                    return Err(error(format!("Unable to solve {}", text)));
+                }
+            },
+        }
+        if session.quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Solves an equation with the session's variables, functions, and format.
+fn solve(equation: &str, session: &Session) -> Result<Value, String> {
+    let tokens = tokenize_with_context(equation, session.context())?;
+    evaluate(infix_to_postfix(&tokens))
+}
+
+// Computes the values of a for loop from its start, end, and optional step.
+// Integer ranges stay exact. Otherwise values are computed as floats.
+fn loop_values(range: &[Value]) -> Result<Vec<Value>, String> {
+    let step = range.get(2).cloned().unwrap_or(Value::Integer(BigInt::from(1)));
+
+    if let (Value::Integer(start), Value::Integer(end), Value::Integer(step)) = (&range[0], &range[1], &step) {
+        if step.is_zero() {
+            return Err("Loop step cannot be 0".to_string());
+        }
+        if (step.is_positive() && end < start) || (step.is_negative() && end > start) {
+            return Ok(Vec::new());
+        }
+        let count: BigInt = (end - start) / step + 1;
+        if count > BigInt::from(MAX_LOOP_ITERATIONS) {
+            return Err(format!("Loop exceeded {} iterations", MAX_LOOP_ITERATIONS));
+        }
+        let count = count.to_usize().unwrap_or(0);
+        return Ok((0..count).map(|index| Value::Integer(start + step * index)).collect());
+    }
+
+    let (start, end, step) = (range[0].to_f64()?, range[1].to_f64()?, step.to_f64()?);
+    if step == 0.0 || !step.is_finite() {
+        return Err(format!("Loop step cannot be {}", step));
+    }
+    // A small tolerance keeps float steps like 0.1 from missing the end value.
+    let count = ((end - start) / step + 1E-9).floor() + 1.0;
+    if count.is_nan() || count > MAX_LOOP_ITERATIONS as f64 {
+        return Err(format!("Loop exceeded {} iterations", MAX_LOOP_ITERATIONS));
+    }
+    let count = count.max(0.0) as usize;
+    Ok((0..count).map(|index| Value::Float(start + step * index as f64)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_arguments_outside_brackets_and_quotes() {
+        assert_eq!(split_arguments("1, S[1, 2, x], \"a, b\"", ','), vec!["1", "S[1, 2, x]", "\"a, b\""]);
+        assert_eq!(split_arguments("1,5; S[1; 2; x]", ';'), vec!["1,5", "S[1; 2; x]"]);
+        assert!(split_arguments("", ',').is_empty());
+    }
+
+    #[test]
+    fn splits_equations_outside_brackets() {
+        assert_eq!(split_equations("1;S[1;2;x] ; 3", true), vec!["1", "S[1;2;x] ", " 3"]);
+        assert_eq!(split_equations("S[1;2", false), vec!["S[1", "2"]);
+        assert_eq!(split_equations("1; /plot x^2; -2; 2", true), vec!["1", " /plot x^2; -2; 2"]);
+        assert_eq!(split_equations("/reveal; 1", false), vec!["/reveal", " 1"]);
+    }
+
+    #[test]
+    fn computes_loop_values() {
+        let integers = |values: &[i32]| values.iter().map(|value| Value::Integer(BigInt::from(*value))).collect::<Vec<Value>>();
+        assert_eq!(loop_values(&integers(&[1, 3])), Ok(integers(&[1, 2, 3])));
+        assert_eq!(loop_values(&integers(&[10, 1, -4])), Ok(integers(&[10, 6, 2])));
+        assert_eq!(loop_values(&integers(&[3, 1])), Ok(Vec::new()));
+        assert_eq!(loop_values(&integers(&[1, 0, 2])), Ok(Vec::new()));
+        assert_eq!(loop_values(&[Value::Float(0.0), Value::Float(0.3), Value::Float(0.1)]).map(|values| values.len()), Ok(4));
+        assert!(loop_values(&integers(&[1, 3, 0])).is_err());
+        assert!(loop_values(&integers(&[0, 10_000_000])).is_err());
+    }
+}
@@ -0,0 +1,477 @@
+use crate::compile::{compile, multiply_variables, CompiledExpr, Env};
+use crate::evaluate::{apply, evaluate, result};
+use crate::infix_to_postfix::infix_to_postfix;
+use crate::tokenize::{tokenize_at_depth, Context};
+use crate::value::Value;
+use num_bigint::BigInt;
+use std::collections::HashMap;
+
+// Maximum number of terms evaluated by a summation or product.
+const MAX_ITERATIONS: i64 = 1_000_000;
+// Infinite series end once this many terms in a row have shrunk fast enough to bound the rest of the
+// series to SERIES_TOLERANCE relative to the result.
+const CONVERGED_TERMS: usize = 10;
+const SERIES_TOLERANCE: f64 = 1E-15;
+// Infinite series that have not ended are extrapolated from the first this many terms in a row that shrink,
+// when the extrapolations agree within ACCELERATION_TOLERANCE.
+const ACCELERATED_TERMS: usize = 20;
+const ACCELERATION_TOLERANCE: f64 = 1E-8;
+// Extrapolated series are summed for this many more terms, and end with the sum if it converges in them.
+const CONFIRMATION_TERMS: i64 = 1000;
+
+// Errors of a summation or product.
+struct SeriesErrors {
+    missing: [&'static str; 3],
+    start: &'static str,
+    upper_limit: &'static str,
+    step: &'static str,
+    equation: &'static str,
+    iterations: &'static str,
+    convergence: &'static str,
+}
+
+const SUMMATION: SeriesErrors = SeriesErrors {
+    missing: ["Missing summation start, upper limit, and equation", "Missing summation upper limit and equation",
+              "Missing summation equation"],
+    start: "Could not evaluate summation start",
+    upper_limit: "Could not evaluate summation upper limit",
+    step: "Summation step must be a number other than 0 that moves from the start toward the upper limit",
+    equation: "Could not evaluate summation equation",
+    iterations: "Too many summation iterations",
+    convergence: "Summation did not converge",
+};
+
+const PRODUCT: SeriesErrors = SeriesErrors {
+    missing: ["Missing product start, upper limit, and equation", "Missing product upper limit and equation",
+              "Missing product equation"],
+    start: "Could not evaluate product start",
+    upper_limit: "Could not evaluate product upper limit",
+    step: "Product step must be a number other than 0 that moves from the start toward the upper limit",
+    equation: "Could not evaluate product equation",
+    iterations: "Too many product iterations",
+    convergence: "Product did not converge",
+};
+
+// Computes a summation 'S' or product 'P' from the text inside its brackets:
+// '[start, upper limit, equation]' or '[start, upper limit, step, equation]'.
+// The start may name the bound variable, as in 'k=1', which is 'x' otherwise. The bounds are evaluated
+// with the caller's variables, and the equation with a copy of them where the bound variable is set,
+// so nested summations like 'S[k=1, 10, k*S[j=1, k, j]]' see the variables of those around them.
+pub(crate) fn series(kind: char, text: &str, context: Context, depth: usize) -> Result<String, String> {
+    let errors = if kind == 'P' { &PRODUCT } else { &SUMMATION };
+    let arguments = split_arguments(text, context.format.locale.argument_separator());
+    let (bounds, equation) = match arguments.len() {
+        length @ 0..=2 => return Err(errors.missing[length].to_string()),
+        3 | 4 => arguments.split_at(arguments.len() - 1),
+        _ => return Err(errors.equation.to_string()),
+    };
+    let (name, start) = bound_variable(bounds[0])?;
+
+    let number = |text: &str, error: &'static str| {
+        let tokens = tokenize_at_depth(text, context, depth).map_err(|_| error)?;
+        evaluate(infix_to_postfix(&tokens)).and_then(|value| value.to_f64()).map_err(|_| error)
+    };
+    let start = number(start, errors.start).ok().filter(|start| start.is_finite()).ok_or(errors.start)?;
+    let upper_limit = number(bounds[1], errors.upper_limit).ok().filter(|limit| !limit.is_nan())
+        .ok_or(errors.upper_limit)?;
+    let step = match bounds.get(2) {
+        Some(step) => number(step, errors.step)?,
+        None if upper_limit < start => -1.0,
+        None => 1.0,
+    };
+    if !step.is_finite() || step == 0.0 || (upper_limit - start) * step < 0.0 {
+        return Err(errors.step.to_string());
+    }
+    let series = Series { start, upper_limit, step, errors };
+
+    let mut substitution = Substitution::new(equation[0], name, context, depth)?;
+    let result = series.compute(kind, |x| substitution.evaluate(x))?;
+    Ok(result.to_string())
+}
+
+// An equation evaluated for many values of a bound variable, with a copy of the caller's variables.
+pub(crate) struct Substitution<'a> {
+    equation: &'a str,
+    name: char,
+    scope: HashMap<char, String>,
+    context: Context<'a>,
+    depth: usize,
+    form: Form,
+}
+
+// How an equation is evaluated for each value of the bound variable.
+enum Form {
+    // Postfix terms, with numbers parsed once and the bound variable left to be replaced.
+    Postfix(Vec<Term>),
+    // An equation with complex operators computed once by compile, with the caller's variables set.
+    Compiled(CompiledExpr, Box<Env>),
+    // An equation whose complex operators use the bound variable, tokenized again for each value.
+    Tokenized,
+}
+
+// A postfix token of an equation in postfix form.
+enum Term {
+    Value(Value),
+    Bound,
+    Operator(String),
+}
+
+impl<'a> Substitution<'a> {
+    pub(crate) fn new(equation: &'a str, name: char, context: Context<'a>, depth: usize)
+                      -> Result<Substitution<'a>, String> {
+        let mut substitution = Substitution {
+            equation,
+            name,
+            scope: context.variables.clone(),
+            context,
+            depth,
+            form: Form::Tokenized,
+        };
+        substitution.form = if equation.contains('[') {
+            substitution.compile().map_or(Form::Tokenized, |(expression, env)| Form::Compiled(expression, Box::new(env)))
+        } else {
+            substitution.scope.insert(name, "x".to_string());
+            let tokens = tokenize_at_depth(equation, Context { variables: &substitution.scope, ..context }, depth)?;
+            // 'x' is not a number to tokenize, so it is multiplied by what follows it here, as in 'x(x+1)'.
+            let terms = infix_to_postfix(&multiply_variables(tokens)).into_iter()
+                .map(|token| match Value::parse(&token) {
+                    _ if token == "x" => Term::Bound,
+                    Some(value) => Term::Value(value),
+                    None => Term::Operator(token),
+                })
+                .collect();
+            Form::Postfix(terms)
+        };
+        Ok(substitution)
+    }
+
+    // Compiles an equation with complex operators, which fails if they use the bound variable or it reads
+    // a variable that is not a number.
+    fn compile(&self) -> Option<(CompiledExpr, Env)> {
+        let expression = compile(self.equation).ok()?;
+        let mut env = Env::new();
+        for &variable in expression.variables().iter().filter(|variable| **variable != self.name) {
+            let value = Value::parse(self.scope.get(&variable)?)?.to_f64().ok()?;
+            env.set(variable, value).ok()?;
+        }
+        Some((expression, env))
+    }
+
+    // Evaluates the equation with the bound variable set to x.
+    pub(crate) fn evaluate(&mut self, x: &Value) -> Result<f64, String> {
+        match &mut self.form {
+            Form::Postfix(terms) => {
+                let mut stack = Vec::new();
+                for term in terms.iter() {
+                    match term {
+                        Term::Value(value) => stack.push(value.clone()),
+                        Term::Bound => stack.push(x.clone()),
+                        Term::Operator(operator) => apply(&mut stack, operator)?,
+                    }
+                }
+                result(stack)?.to_f64()
+            },
+            Form::Compiled(expression, env) => {
+                env.set(self.name, x.to_f64()?)?;
+                expression.eval(env)
+            },
+            Form::Tokenized => {
+                self.scope.insert(self.name, x.to_string());
+                let context = Context { variables: &self.scope, ..self.context };
+                let tokens = tokenize_at_depth(self.equation, context, self.depth)?;
+                evaluate(infix_to_postfix(&tokens))?.to_f64()
+            },
+        }
+    }
+}
+
+// Splits the text inside brackets at separators outside of nested brackets. Empty values are left out.
+pub(crate) fn split_arguments(text: &str, separator: char) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (position, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ if c == separator && depth == 0 => {
+                arguments.push(&text[start..position]);
+                start = position + c.len_utf8();
+            },
+            _ => {},
+        }
+    }
+    arguments.push(&text[start..]);
+    arguments.retain(|argument| !argument.trim().is_empty());
+    arguments
+}
+
+// Reads the bound variable named at the start of a summation or product, as in 'k=1', and returns it
+// with the rest of the start. Names are single lowercase letters other than the constants p and e.
+fn bound_variable(start: &str) -> Result<(char, &str), String> {
+    let mut chars = start.trim_start().chars();
+    let Some(name) = chars.next().filter(char::is_ascii_alphabetic) else { return Ok(('x', start)) };
+    let Some(value) = chars.as_str().trim_start().strip_prefix('=').filter(|value| !value.starts_with('=')) else {
+        return Ok(('x', start));
+    };
+    if !name.is_ascii_lowercase() || name == 'p' || name == 'e' {
+        return Err(format!("{} cannot be bound. Bound variables are lowercase letters other than p and e", name));
+    }
+    Ok((name, value))
+}
+
+// The values a summation or product's bound variable takes, from start toward the upper limit by step.
+// The step is 1, or -1 when the upper limit is below the start. An upper limit of 'inf' or '-inf'
+// continues until the rest of the series is negligible, or is extrapolated when the terms shrink slowly.
+struct Series {
+    start: f64,
+    upper_limit: f64,
+    step: f64,
+    errors: &'static SeriesErrors,
+}
+
+impl Series {
+    // Combines the value of each term into a sum or product.
+    fn compute(&self, kind: char, mut term: impl FnMut(&Value) -> Result<f64, String>) -> Result<f64, &'static str> {
+        if self.upper_limit.is_infinite() {
+            return self.compute_infinite(kind, term);
+        }
+        // A little is added so that rounding in fractional steps does not leave out the upper limit.
+        let terms = ((self.upper_limit - self.start) / self.step + 1E-9).floor() + 1.0;
+        if terms > MAX_ITERATIONS as f64 {
+            return Err(self.errors.iterations);
+        }
+
+        let mut result = if kind == 'P' { 1.0 } else { 0.0 };
+        for index in 0..terms as i64 {
+            let value = term(&self.value(index)).map_err(|_| self.errors.equation)?;
+            if kind == 'P' {
+                result *= value;
+            } else {
+                result += value;
+            }
+        }
+        Ok(result)
+    }
+
+    // Sums an infinite series until its terms shrink fast enough that the rest of it is negligible. Series
+    // that converge too slowly for that are extrapolated from the first ACCELERATED_TERMS terms in a row
+    // that shrink, and rounded to the digits the extrapolations agree on, unless the sum converges in the
+    // CONFIRMATION_TERMS terms after them. Products sum the logarithms of their terms.
+    fn compute_infinite(&self, kind: char, mut term: impl FnMut(&Value) -> Result<f64, String>)
+                        -> Result<f64, &'static str> {
+        let product = kind == 'P';
+        let (mut sum, mut negative) = (0.0, false);
+        let (mut sums, mut terms) = (Vec::new(), Vec::new());
+        let (mut previous, mut flipped): (Option<f64>, bool) = (None, false);
+        let (mut shrinking_terms, mut largest_ratio) = (0, 0.0);
+        let mut extrapolated: Option<(f64, i64)> = None;
+        let finish = |sum: f64, negative: bool| {
+            match (product, negative) {
+                (false, _) => sum,
+                (true, false) => sum.exp(),
+                (true, true) => -sum.exp(),
+            }
+        };
+
+        for index in 0..MAX_ITERATIONS {
+            if let Some((result, _)) = extrapolated.filter(|(_, end)| *end == index) {
+                return Ok(result);
+            }
+            let mut value = term(&self.value(index)).map_err(|_| self.errors.equation)?;
+            if product {
+                if value == 0.0 {
+                    return Ok(0.0);
+                }
+                flipped = value < 0.0;
+                negative ^= flipped;
+                value = value.abs().ln();
+            }
+            sum += value;
+            if !sum.is_finite() {
+                return Err(self.errors.convergence);
+            }
+
+            // Terms that keep shrinking by at most the largest ratio between them leave less than
+            // term * ratio / (1 - ratio) of the series.
+            let ratio = match previous {
+                // A negative factor changes the sign of the product however close to -1 it is.
+                _ if flipped => f64::INFINITY,
+                _ if value == 0.0 => 0.0,
+                Some(previous) if previous != 0.0 => (value / previous).abs(),
+                _ => f64::INFINITY,
+            };
+            if ratio < 1.0 {
+                shrinking_terms += 1;
+                largest_ratio = f64::max(largest_ratio, ratio);
+            } else {
+                (shrinking_terms, largest_ratio) = (0, 0.0);
+                extrapolated = None;
+                if sums.len() < ACCELERATED_TERMS {
+                    sums.clear();
+                    terms.clear();
+                }
+            }
+            let scale = if product { 1.0 } else { sum.abs() };
+            if shrinking_terms >= CONVERGED_TERMS
+                && value.abs() * largest_ratio / (1.0 - largest_ratio) <= SERIES_TOLERANCE * scale {
+                return Ok(finish(sum, negative));
+            }
+            previous = Some(value);
+
+            if sums.len() < ACCELERATED_TERMS {
+                sums.push(sum);
+                terms.push(value);
+                if sums.len() == ACCELERATED_TERMS {
+                    extrapolated = accelerate(&sums, &terms, product).map(|(estimate, digits)| {
+                        (round_significant(finish(estimate, negative), digits), index + CONFIRMATION_TERMS)
+                    });
+                }
+            }
+        }
+        Err(self.errors.convergence)
+    }
+
+    // The value of the bound variable for a term. Whole number values are substituted as integers,
+    // so equations like 'x!' stay exact.
+    fn value(&self, index: i64) -> Value {
+        let whole = self.start.fract() == 0.0 && self.step.fract() == 0.0
+            && (self.start.abs() + self.step.abs() * MAX_ITERATIONS as f64) < (1u64 << 53) as f64;
+        if whole {
+            Value::Integer(BigInt::from(self.start as i64 + index * self.step as i64))
+        } else {
+            Value::Float(self.start + index as f64 * self.step)
+        }
+    }
+}
+
+// Extrapolates the limit of partial sums from shrinking terms with the Levin u-transform, which handles
+// series like 1/x^2 and alternating series that converge too slowly to sum. It is only used for terms that
+// keep or alternate their sign, and the limit must lie beyond the partial sums or between the last two of them,
+// so divergent series are not given a value. Returns the estimate that agrees best with the two before it
+// and its number of significant digits, or None if they do not agree within ACCELERATION_TOLERANCE.
+// Differences are absolute for products, which are sums of logarithms.
+fn accelerate(sums: &[f64], terms: &[f64], product: bool) -> Option<(f64, i32)> {
+    let same_sign = terms.iter().all(|term| (*term > 0.0) == (terms[0] > 0.0));
+    let alternating = terms.windows(2).all(|pair| (pair[0] > 0.0) != (pair[1] > 0.0));
+    if !(same_sign || alternating) {
+        return None;
+    }
+
+    // Each estimate is compared with the two before it, so that two estimates agreeing by chance are not used.
+    // Its digits are those it shares with the one before it.
+    let estimates: Vec<f64> = (0..terms.len()).map(|k| levin(sums, terms, k)).collect();
+    let (agreement, difference, estimate) = (4..estimates.len())
+        .map(|k| {
+            let scale = if product { 1.0 } else { estimates[k].abs() };
+            let difference = (estimates[k] - estimates[k - 1]).abs() / scale;
+            let agreement = difference.max((estimates[k - 1] - estimates[k - 2]).abs() / scale);
+            (agreement, difference, estimates[k])
+        })
+        .filter(|(agreement, _, _)| !agreement.is_nan())
+        .min_by(|a, b| a.0.total_cmp(&b.0))?;
+    if agreement > ACCELERATION_TOLERANCE {
+        return None;
+    }
+
+    let (last, before) = (sums[sums.len() - 1], sums[sums.len() - 2]);
+    let consistent = if same_sign {
+        (estimate - last) * terms[terms.len() - 1] >= 0.0
+    } else {
+        estimate >= last.min(before) && estimate <= last.max(before)
+    };
+    let digits = (-difference.log10()).floor().clamp(1.0, 17.0) as i32;
+    consistent.then_some((estimate, digits))
+}
+
+// The Levin u-transform of the first k + 1 partial sums.
+fn levin(sums: &[f64], terms: &[f64], k: usize) -> f64 {
+    let (mut numerator, mut denominator) = (0.0, 0.0);
+    let mut binomial = 1.0;
+    for j in 0..=k {
+        let position = (j + 1) as f64;
+        let weight = binomial * (position / (k + 1) as f64).powi(k as i32 - 1) / (position * terms[j]);
+        let weight = if j % 2 == 0 { weight } else { -weight };
+        numerator += weight * sums[j];
+        denominator += weight;
+        binomial *= (k - j) as f64 / position;
+    }
+    numerator / denominator
+}
+
+// Rounds a value to a number of significant digits.
+fn round_significant(value: f64, digits: i32) -> f64 {
+    format!("{:.*e}", digits as usize - 1, value).parse().unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::NumberFormat;
+    use crate::uncertainty::Propagation;
+    use std::f64::consts;
+
+    #[test]
+    fn splits_arguments_outside_of_brackets() {
+        assert_eq!(split_arguments("k=1, 3, k*S[j=1, k, j]", ','), vec!["k=1", " 3", " k*S[j=1, k, j]"]);
+        assert_eq!(split_arguments("1; 2,5;; x", ';'), vec!["1", " 2,5", " x"]);
+    }
+
+    fn infinite(kind: char, start: f64, term: impl Fn(f64) -> f64) -> Result<f64, &'static str> {
+        let errors = if kind == 'P' { &PRODUCT } else { &SUMMATION };
+        let series = Series { start, upper_limit: f64::INFINITY, step: 1.0, errors };
+        series.compute(kind, |x| Ok(term(x.to_f64().unwrap())))
+    }
+
+    #[test]
+    fn extrapolates_slowly_converging_series() {
+        let zeta = infinite('S', 1.0, |x| x.powf(-1.5)).unwrap();
+        assert!((zeta - 2.612375348685488).abs() < 1E-8, "{}", zeta);
+        assert_eq!(zeta, round_significant(zeta, 9));
+        let product = infinite('P', 1.0, |x| 1.0 - 2.0 / (x * x)).unwrap();
+        assert!((product - (consts::PI * consts::SQRT_2).sin() / (consts::PI * consts::SQRT_2)).abs() < 1E-8);
+        // Series whose terms shrink quickly once they start to are summed in full after extrapolating.
+        let exponential = infinite('S', 0.0, |x| 30f64.powf(x) / (1..=x as u64).map(|n| n as f64).product::<f64>());
+        assert!((exponential.unwrap() / 30f64.exp() - 1.0).abs() < 1E-14);
+    }
+
+    #[test]
+    fn divergent_series_are_errors() {
+        assert_eq!(infinite('S', 1.0, |x| 1.0 / x), Err(SUMMATION.convergence));
+        assert_eq!(infinite('S', 1.0, |x| x.powf(-0.5)), Err(SUMMATION.convergence));
+        assert_eq!(infinite('S', 0.0, |x| (-1f64).powf(x)), Err(SUMMATION.convergence));
+        assert_eq!(infinite('P', 1.0, |_| -1.0), Err(PRODUCT.convergence));
+    }
+
+    #[test]
+    fn substitutes_without_tokenizing_each_value() {
+        let variables = HashMap::from([('i', "4".to_string()), ('j', "[1, 2]".to_string())]);
+        let context = Context {
+            variables: &variables,
+            functions: &HashMap::new(),
+            format: &NumberFormat::default(),
+            results: &[],
+            uncertainty: Propagation::Gaussian,
+        };
+        let cases = [("x(x+1) - i", 8.0), ("i*x + isprime[7]", 13.0), ("S[1, x, x] + i", 10.0), ("x*isprime[4] + i", 4.0)];
+        for (equation, expected) in cases {
+            let mut substitution = Substitution::new(equation, 'x', context, 0).unwrap();
+            assert_eq!(substitution.evaluate(&Value::Integer(BigInt::from(3))), Ok(expected), "{}", equation);
+        }
+        let form = |equation| Substitution::new(equation, 'k', context, 0).unwrap().form;
+        assert!(matches!(form("k^2"), Form::Postfix(_)));
+        assert!(matches!(form("k*isprime[7]"), Form::Compiled(..)));
+        assert!(matches!(form("isprime[k]"), Form::Tokenized));
+        // Lists cannot be compiled, so equations reading them are tokenized.
+        assert!(matches!(form("k*isprime[7] + j"), Form::Tokenized));
+    }
+
+    #[test]
+    fn reads_bound_variables() {
+        assert_eq!(bound_variable("k=1"), Ok(('k', "1")));
+        assert_eq!(bound_variable(" j = k+1"), Ok(('j', " k+1")));
+        assert_eq!(bound_variable("1"), Ok(('x', "1")));
+        assert_eq!(bound_variable("i==1"), Ok(('x', "i==1")));
+        assert!(bound_variable("e=1").is_err());
+        assert!(bound_variable("K=1").is_err());
+    }
+}
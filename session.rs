@@ -0,0 +1,177 @@
+use crate::command::get_variable_map;
+use crate::config::Config;
+use crate::function::{parse_definition, Function};
+use crate::settings::{self, Settings};
+use crate::tokenize::Context;
+use crate::undo::Changes;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Maximum number of history lines written to a session file.
+const HISTORY_LIMIT: usize = 1000;
+
+// Section headers of a session file.
+const SECTIONS: [&str; 5] = ["[variables]", "[functions]", "[settings]", "[results]", "[history]"];
+
+// Calculator state that persists between launches.
+pub struct Session {
+    pub variables: HashMap<char, String>,
+    pub functions: HashMap<String, Function>,
+    pub settings: Settings,
+    pub results: Vec<Calculation>,
+    pub history: Vec<String>,
+    pub changes: Changes,
+    pub config: Config,
+    pub quit: bool,
+}
+
+// A solved equation and its result, referenced by number as '$1', '$2', ...
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calculation {
+    pub input: String,
+    pub result: String,
+}
+
+impl Session {
+    // Initializes a session with default variables and settings.
+    pub fn new() -> Session {
+        Session {
+            variables: get_variable_map(),
+            functions: HashMap::new(),
+            settings: Settings::default(),
+            results: Vec::new(),
+            history: Vec::new(),
+            changes: Changes::default(),
+            config: Config::default(),
+            quit: false,
+        }
+    }
+
+    // Returns the values equations are tokenized with.
+    pub fn context(&self) -> Context<'_> {
+        Context {
+            variables: &self.variables,
+            functions: &self.functions,
+            format: &self.settings.format,
+            results: &self.results,
+            uncertainty: self.settings.uncertainty,
+        }
+    }
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session::new()
+    }
+}
+
+// Returns the directory Rusty Calculator stores its data in, following the XDG base directory spec.
+pub fn data_directory() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME").filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+    };
+    Some(base.join("rusty_calculator"))
+}
+
+// Returns the path of the session restored on startup and saved on /x.
+pub fn default_path() -> Option<PathBuf> {
+    data_directory().map(|directory| directory.join("session"))
+}
+
+// Writes mutable variables, the last answer, functions, settings, results, and history to a session file.
+pub fn save(session: &Session, path: &Path) -> Result<(), String> {
+    let mut contents = String::from("[variables]\n");
+    let mut variables: Vec<(&char, &String)> = session.variables.iter()
+        .filter(|(key, _)| is_saved_variable(**key))
+        .collect();
+    variables.sort();
+    for (key, value) in variables {
+        contents.push_str(&format!("{}={}\n", key, value));
+    }
+
+    contents.push_str("[functions]\n");
+    let mut functions: Vec<(&String, &Function)> = session.functions.iter().collect();
+    functions.sort_by_key(|(name, _)| *name);
+    for (name, function) in functions {
+        contents.push_str(&function.definition(name));
+        contents.push('\n');
+    }
+
+    contents.push_str("[settings]\n");
+    for name in settings::NAMES {
+        contents.push_str(&format!("{}={}\n", name, session.settings.get(name)?));
+    }
+
+    // Results are written before their input, which may contain any character but a line break.
+    contents.push_str("[results]\n");
+    let skipped = session.results.len().saturating_sub(HISTORY_LIMIT);
+    for calculation in &session.results[skipped..] {
+        contents.push_str(&format!("{}\t{}\n", calculation.result, calculation.input));
+    }
+
+    contents.push_str("[history]\n");
+    let skipped = session.history.len().saturating_sub(HISTORY_LIMIT);
+    for line in &session.history[skipped..] {
+        contents.push_str(line);
+        contents.push('\n');
+    }
+
+    if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        fs::create_dir_all(directory)
+            .map_err(|e| format!("Unable to create {}: {}", directory.display(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+}
+
+// Reads a session file written by save. Missing entries keep their default values.
+pub fn load(path: &Path) -> Result<Session, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    let mut session = Session::new();
+    let mut section = "";
+
+    for (number, line) in contents.lines().enumerate() {
+        // History is always the last section, so its lines are never headers.
+        if section != "[history]" && SECTIONS.contains(&line) {
+            section = line;
+            continue;
+        }
+        let malformed = || format!("Malformed line {} in {}: {}", number + 1, path.display(), line);
+        match section {
+            "[variables]" => {
+                let mut chars = line.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(key), Some('=')) if is_saved_variable(key) => {
+                        session.variables.insert(key, chars.as_str().to_string());
+                    },
+                    _ => return Err(malformed()),
+                }
+            },
+            "[functions]" => {
+                let (name, function) = parse_definition(line).map_err(|_| malformed())?;
+                session.functions.insert(name, function);
+            },
+            "[settings]" => {
+                let (name, value) = line.split_once('=').ok_or_else(malformed)?;
+                session.settings.set(name, value).map_err(|_| malformed())?;
+            },
+            "[results]" => {
+                let (result, input) = line.split_once('\t').ok_or_else(malformed)?;
+                let calculation = Calculation { input: input.to_string(), result: result.to_string() };
+                session.results.push(calculation);
+            },
+            "[history]" => session.history.push(line.to_string()),
+            _ if line.is_empty() => {},
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(session)
+}
+
+// Only user variables and the last answer are saved. Constants always use their defaults.
+fn is_saved_variable(key: char) -> bool {
+    matches!(key, 'i'..='o' | '=')
+}
@@ -0,0 +1,189 @@
+use crate::format::{Locale, Notation, NumberFormat, Rounding, MAX_DIGITS};
+use crate::probability;
+use crate::uncertainty::Propagation;
+
+// Names of the settings changed with /set and shown with /get, in the order they are listed.
+pub const NAMES: [&str; 9] = ["reveal", "output", "notation", "precision", "grouping", "rounding", "locale",
+                              "uncertainty", "seed"];
+
+// How results are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    Text,
+    Json,
+}
+
+// Calculator settings. Each is read and changed by name as text, so they can be entered with /set
+// and saved with the session.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub reveal: bool,
+    pub output: Output,
+    pub format: NumberFormat,
+    pub uncertainty: Propagation,
+    // Seed of the random number generator, or None to seed it from the system.
+    pub seed: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            reveal: false,
+            output: Output::Text,
+            format: NumberFormat::default(),
+            uncertainty: Propagation::Gaussian,
+            seed: None,
+        }
+    }
+}
+
+impl Settings {
+    // Whether results are written as JSON objects.
+    pub fn json(&self) -> bool {
+        self.output == Output::Json
+    }
+
+    // Returns the value of a setting in the form /set accepts.
+    pub fn get(&self, name: &str) -> Result<String, String> {
+        let format = &self.format;
+        let value = match name {
+            "reveal" => on_off(self.reveal).to_string(),
+            "output" => if self.json() { "json" } else { "text" }.to_string(),
+            "notation" => format.notation.to_string(),
+            "precision" => format.digits.to_string(),
+            "grouping" => on_off(format.grouping).to_string(),
+            "rounding" => format.rounding.to_string(),
+            "locale" => format.locale.to_string(),
+            "uncertainty" => self.uncertainty.to_string(),
+            "seed" => self.seed.map_or("random".to_string(), |seed| seed.to_string()),
+            _ => return Err(unknown(name)),
+        };
+        Ok(value)
+    }
+
+    // Changes a setting, leaving it unchanged if the value is not valid for it.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let format = &mut self.format;
+        match name {
+            "reveal" => self.reveal = parse_switch(name, value)?,
+            "output" => {
+                self.output = match value {
+                    "text" => Output::Text,
+                    "json" => Output::Json,
+                    _ => return Err(format!("{} is not a valid output. Try text or json", value)),
+                };
+            },
+            // Digits may follow the notation, as in 'sci 3'.
+            "notation" if value.contains(' ') => format.set_notation(value)?,
+            "notation" => {
+                let notation = Notation::parse(value).ok_or_else(|| {
+                    format!("{} is not a valid notation. Try auto, fixed, sig, sci, or eng", value)
+                })?;
+                check_precision(notation, format.digits)?;
+                format.notation = notation;
+            },
+            "precision" => {
+                let digits = value.parse::<usize>().ok().filter(|digits| *digits <= MAX_DIGITS)
+                    .ok_or_else(|| format!("{} is not a valid precision. Try a whole number from 0 to {}",
+                                           value, MAX_DIGITS))?;
+                check_precision(format.notation, digits)?;
+                format.digits = digits;
+            },
+            "grouping" => format.grouping = parse_switch(name, value)?,
+            "rounding" => {
+                format.rounding = Rounding::parse(value).ok_or_else(|| {
+                    format!("{} is not a valid rounding mode. Try half-up, half-even, half-down, up, down, \
+                             floor, or ceiling", value)
+                })?;
+            },
+            "locale" => {
+                format.locale = Locale::parse(value)
+                    .ok_or_else(|| format!("{} is not a valid locale. Try en, de, or fr", value))?;
+            },
+            "uncertainty" => {
+                self.uncertainty = Propagation::parse(value).ok_or_else(|| {
+                    format!("{} is not a valid uncertainty propagation. Try gaussian or interval", value)
+                })?;
+            },
+            // Setting a seed restarts the random number generator, so the same draws follow.
+            "seed" => {
+                self.seed = match value {
+                    "random" => None,
+                    _ => Some(value.parse::<u64>().map_err(|_| {
+                        format!("{} is not a valid seed. Try a whole number of at least 0, or random", value)
+                    })?),
+                };
+                probability::seed(self.seed);
+            },
+            _ => return Err(unknown(name)),
+        }
+        Ok(())
+    }
+}
+
+// Significant digit notations show at least one digit. Only fixed notation may show none.
+fn check_precision(notation: Notation, digits: usize) -> Result<(), String> {
+    match notation {
+        Notation::Significant | Notation::Scientific | Notation::Engineering if digits == 0 => {
+            Err(format!("{} needs a precision of at least 1", notation))
+        },
+        _ => Ok(()),
+    }
+}
+
+// Parses an on or off setting. true and false are accepted as well.
+fn parse_switch(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        _ => Err(format!("{} is not valid for {}. Try on or off", value, name)),
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+fn unknown(name: &str) -> String {
+    format!("{} is not a setting. Try {}", name, NAMES.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_by_name() {
+        let mut settings = Settings::default();
+        for (name, value) in [("reveal", "on"), ("output", "json"), ("notation", "sci"), ("precision", "3"),
+                              ("grouping", "on"), ("rounding", "floor"), ("locale", "de"),
+                              ("uncertainty", "interval"), ("seed", "42"), ("seed", "random")] {
+            settings.set(name, value).unwrap();
+            assert_eq!(settings.get(name).unwrap(), value);
+        }
+        assert!(settings.json());
+        assert_eq!(settings.format.notation_name(), "sci 3");
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.set("reveal", "maybe").is_err());
+        assert!(settings.set("precision", "-1").is_err());
+        assert!(settings.set("precision", "101").is_err());
+        assert!(settings.set("notation", "sci 0").is_err());
+        assert!(settings.set("quit", "on").is_err());
+        assert!(settings.set("uncertainty", "normal").is_err());
+        assert!(settings.set("seed", "-1").is_err());
+        assert!(settings.get("quit").is_err());
+
+        // Zero digits are only valid for fixed notation.
+        settings.set("notation", "fixed 0").unwrap();
+        assert!(settings.set("notation", "sig").is_err());
+        settings.set("precision", "2").unwrap();
+        settings.set("notation", "sig").unwrap();
+        assert!(settings.set("precision", "0").is_err());
+        assert_eq!(settings, Settings { format: settings.format, ..Settings::default() });
+        assert_eq!(settings.format.notation_name(), "sig 2");
+    }
+}
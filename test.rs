@@ -1,109 +1,194 @@
 use std::collections::HashMap;
+use std::mem;
+use std::f64::consts;
 use crate::tokenize::tokenize;
 use crate::infix_to_postfix::infix_to_postfix;
 use crate::evaluate::evaluate;
+use crate::value::Value;
 
-// Executes a test equation and returns the final result.
-fn compute_result(test: &str) -> String {
+// Relative tolerance used when comparing numerical results.
+const TOLERANCE: f64 = 1E-9;
+
+// Test equations and their expected final result, run in order by /test and cargo test.
+pub const CASES: &[(&str, &str)] = &[
+    ("2+2", "4"),
+    ("2-+2", "0"),
+    ("2--2", "4"),
+    ("2++2", "4"),
+    ("-4+4", "0"),
+    ("-(4+4)", "-8"),
+    ("-4*-4", "16"),
+    ("-4/-4", "1"),
+    ("4*-4", "-16"),
+    ("-4/4", "-1"),
+    ("5!^2+3", "14403"),
+    ("-2+10/2*40+5!", "318"),
+    ("4 4", "16"),
+    ("(5+5)(5+5)", "100"),
+    ("(5+5)5+5", "55"),
+    ("5+5(5+5)", "55"),
+    ("(-4+4)", "0"),
+    ("-4//3+4#3", "0"),
+    ("30%%100 + 30\\100", "60"),
+    ("=5;=+5;=5;=^2;=/2", "312.5"),
+    ("-e", "-2.718281828459045"),
+    ("pe", "8.539734222673566"),
+    ("p e", "8.539734222673566"),
+    ("1;p=e=======", "8.539734222673566"),
+    ("R2^2", "2"),
+    ("-2^3;-=", "8"),
+    ("-2^2;(-=)^2", "16"),
+    ("-3R2^3", "0.5"),
+    ("2*1R4", "8"),
+    ("(R(R(R(R4))))^2^2^2^2", "3.9999999999999982"),
+    ("(-3)R8+1", "1.5"),
+    ("RL10", "2"),
+    ("R(L10)", "1"),
+    ("5Ne", "5"),
+    ("Ne-eLe", "0"),
+    ("Ne==1 and @tau==2p", "1"),
+    ("@phys.c@k_B", "4.139177266734602E-15"),
+    ("3H4-R(4^2+3^2)", "0"),
+    ("-3H-4-R((-4)^2+(-3)^2)", "0"),
+    ("S[0, 4, 4] - (4+4+4+4+4)", "0"),
+    ("4S[0, 3, 3]", "48"),
+    ("P[1, 3, 3]4", "108"),
+    ("S[0, 9, x^2]-(1+4+9+16+25+36+49+64+81)", "0"),
+    ("P[S[P[1, 3, x+1], P[1, 3, x+2], x+3], 1668, R(x-50)]", "2613070.9999998086"),
+    ("2(2(2(2(2", "32"),
+    ("S[0, 2(2(2+2, 2]", "34"),
+    ("M[4E10,2,3.4]", "13333333335.133333"),
+    ("M[1+2,3+4,5/6,5^3,-5,43*2]", "36.13888888888889"),
+    ("O[1, 2, 3, 4, 5]", "1.4142135623730951"),
+    ("O[1+3, 4+7, 5/10, R25,4^2,-50]","21.938835429438818"),
+    ("Q[3+3,-17,R144]", "48"),
+    ("5A-1", "5"),
+    ("A(5-6+A(5-50)", "44"),
+    ("2^100", "1267650600228229401496703205376"),
+    ("25!", "15511210043330985984000000"),
+    ("gcd[12, 18, 30]", "6"),
+    ("lcm[4, 6, 10]", "60"),
+    ("isprime[2^61-1]+isprime[91]", "1"),
+    ("factor[-360]", "[-1, 2, 2, 2, 3, 3, 5]"),
+    ("factor[2^64+1]", "[274177, 67280421310721]"),
+    ("modpow[2, 100, 1000000007]", "976371285"),
+    ("modinv[3, 11]", "4"),
+    ("ncr[100, 50]", "100891344545564193334812497256"),
+    ("npr[5, 2]-ncr[2, 5]", "20"),
+    ("2+2==4 and not 3<1", "1"),
+    ("1<=0 or 2^64>=2^64+1", "0"),
+    ("5!=120", "1"),
+    ("5! != 120", "0"),
+    ("5!==120", "1"),
+    ("-1>-2", "1"),
+    ("if[2>1, 4, 1/0]if[0, 1, 3]", "12"),
+    ("S[1, 10, x*(x%2==1)]", "25"),
+    ("[[1, 2], [3, 4]]*[[5, 6], [7, 8]]", "[[19, 22], [43, 50]]"),
+    ("2[1, 2]+[1, 1]*[[1, 0], [0, 1]]", "[3, 5]"),
+    ("det[[[1, 2], [3, 4]]]*inv[[[2, 0], [0, 4]]]", "[[-1, 0], [0, -0.5]]"),
+    ("solve[[[2, 1], [1, 3]], [3, 5]]", "[0.8, 1.4]"),
+    ("eig[[[4, 1], [2, 3]]]*rank[[[1, 2], [2, 4]]]", "[5, 2]"),
+    ("({2024-12-25} - {2024-03-01})/{1 day}", "299"),
+    ("{2024-03-01T12:00Z}+{36h} == {2024-03-03T00:00Z}", "1"),
+    ("2{1h 30m}/{1 min}", "180"),
+    ("{1 week} > {6 days 23h}", "1"),
+    ("pmt[0.06/12, 360, 200000]", "-1199.1010503055138"),
+    ("npv[irr[-100, 60, 60], [-100, 60, 60]]", "0"),
+    ("fv[[0.05, 0], 10, 0, -1000]", "[1628.894626777442, 1000]"),
+    ("(9.81±0.02)*2", "19.62±0.04"),
+    ("2(3±0.1)^2 - 2±0.2", "16±1.2165525060596438"),
+    ("[1±0.5, 2]*[2, 2±1]", "6±2.23606797749979"),
+    ("norminv[normcdf[1.5, 10, 2], 10, 2]", "1.5"),
+    ("binomcdf[2, 4, 0.5] + poissonpdf[0, 2]", "0.8228352832366127"),
+    ("S[1, 100, randint[2, 2]] + (rand[] < 1)", "201"),
+    ("S[0.5, 2.5, x] + S[0, 1, 0.25, x] + S[10, 1, -3, x]", "29"),
+    ("S[0, inf, 1/x!]", "2.718281828459045"),
+    ("S[1, inf, 1/x^2]", "1.6449340668482264"),
+    ("S[1, inf, 1/x^3]", "1.2020569031595942"),
+    ("S[1, inf, (-1)^(x+1)/x]", "0.6931471805599453"),
+    ("P[2, inf, 1 - 1/x^2]", "0.5"),
+    ("P[3, 1, x] * P[1, inf, 1 + 1/2^x]", "14.305386174188206"),
+    ("S[k=1, 10, k*S[j=1, k, j]]", "1705"),
+    ("S[1, 3, S[1, x, x]] + S[1, 10, isprime[x]]", "14"),
+    ("100; S[k=1, 3, k] + =", "106"),
+    ("S[k=1, 3, k(k+1)]", "20"),
+    ("S[k=1, 3, 2k]", "12"),
+    ("S[1, 3, xx] + S[1, 3, x2]", "26"),
+];
+
+// Executes a test equation and returns the final result, or the first error.
+pub fn compute_result(test: &str) -> Result<String, String> {
     let mut variables: HashMap<char, String> = [
-        ('p', "3.14159265359".to_string()),
-        ('e', "2.71828182845".to_string()),
+        ('p', consts::PI.to_string()),
+        ('e', consts::E.to_string()),
         ('=', "0".to_string()),
     ].iter().cloned().collect();
 
-    println!("Test: {}", test);
     let equations: Vec<&str> = test.split(";").collect();
     for equation in equations {
-        match tokenize(equation, &variables) {
-            Ok(tokens) => {
-                match evaluate(infix_to_postfix(&tokens)) {
-                    Ok(result) => {
-                        let answer = result.to_string();
-                        variables.insert('=', answer.clone());
-                    },
-                    Err(e) => println!("Error: {}", e),
-                }
-            },
-            Err(e) => println!("Error: {}", e),
-        }
+        let tokens = tokenize(equation, &variables)?;
+        let result = evaluate(infix_to_postfix(&tokens))?;
+        variables.insert('=', result.to_string());
     }
 
     let result = variables.get(&'=').unwrap();
-    result.to_string()
+    Ok(result.to_string())
 }
 
-// Runs all tests.
-pub fn run_tests() {
-    println!("Starting tests...");
+// Compares a result to its expected value. Numbers are equal within a relative tolerance.
+pub fn results_match(result: &str, expected: &str) -> bool {
+    match (Value::parse(result), Value::parse(expected)) {
+        (Some(result), Some(expected)) => values_match(&result, &expected),
+        _ => result == expected,
+    }
+}
+
+// Compares values, recursing into lists.
+fn values_match(result: &Value, expected: &Value) -> bool {
+    match (result, expected) {
+        (Value::Integer(result), Value::Integer(expected)) => result == expected,
+        (Value::List(result), Value::List(expected)) => result.len() == expected.len()
+            && result.iter().zip(expected).all(|(result, expected)| values_match(result, expected)),
+        (Value::Uncertain(result), Value::Uncertain(expected)) => {
+            let ((result_low, result_high), (expected_low, expected_high)) = (result.bounds(), expected.bounds());
+            mem::discriminant(result) == mem::discriminant(expected)
+                && floats_match(result_low, expected_low) && floats_match(result_high, expected_high)
+        },
+        (result, expected) => match (result.to_f64(), expected.to_f64()) {
+            (Ok(result), Ok(expected)) => floats_match(result, expected),
+            _ => result == expected,
+        },
+    }
+}
+
+fn floats_match(result: f64, expected: f64) -> bool {
+    result == expected || (result - expected).abs() <= TOLERANCE * expected.abs().max(1.0)
+}
+
+// Runs all tests and returns a report of each test and any failures.
+pub fn run_tests() -> String {
+    let mut lines = vec!["Starting tests...".to_string()];
     let mut success = 0;
     let mut failure = 0;
 
-    let tests: HashMap<&str, &str> = [
-        ("2+2", "4"),
-        ("2-+2", "0"),
-        ("2--2", "4"),
-        ("2++2", "4"),
-        ("-4+4", "0"),
-        ("-(4+4)", "-8"),
-        ("-4*-4", "16"),
-        ("-4/-4", "1"),
-        ("4*-4", "-16"),
-        ("-4/4", "-1"),
-        ("5!^2+3", "14403"),
-        ("-2+10/2*40+5!", "318"),
-        ("4 4", "16"),
-        ("(5+5)(5+5)", "100"),
-        ("(5+5)5+5", "55"),
-        ("5+5(5+5)", "55"),
-        ("(-4+4)", "0"),
-        ("-4//3+4#3", "0"),
-        ("30%%100 + 30\\100", "60"),
-        ("=5;=+5;=5;=^2;=/2", "312.5"),
-        ("-e", "-2.71828182845"),
-        ("pe", "8.539734222645713"),
-        ("p e", "8.539734222645713"),
-        ("1;p=e=======", "8.539734222645713"),
-        ("R2^2", "2"),
-        ("-2^3;-=", "8"),
-        ("-2^2;(-=)^2", "16"),
-        ("-3R2^3", "0.5"),
-        ("2*1R4", "8"),
-        ("(R(R(R(R4))))^2^2^2^2", "3.9999999999999982"),
-        ("(-3)R8+1", "1.5"),
-        ("RL10", "2"),
-        ("R(L10)", "1"),
-        ("5Ne", "5"),
-        ("Ne-eLe", "0"),
-        ("3H4-R(4^2+3^2)", "0"),
-        ("-3H-4-R((-4)^2+(-3)^2)", "0"),
-        ("S[0, 4, 4] - (4+4+4+4+4)", "0"),
-        ("4S[0, 3, 3]", "48"),
-        ("P[1, 3, 3]4", "108"),
-        ("S[0, 9, x^2]-(1+4+9+16+25+36+49+64+81)", "0"),
-        ("P[S[P[1, 3, x+1], P[1, 3, x+2], x+3], 1668, R(x-50)]", "2613070.9999998086"),
-        ("2(2(2(2(2", "32"),
-        ("S[0, 2(2(2+2, 2]", "34"),
-        ("M[4E10,2,3.4]", "13333333335.133333"),
-        ("M[1+2,3+4,5/6,5^3,-5,43*2]", "36.13888888888889"),
-        ("O[1, 2, 3, 4, 5]", "1.4142135623730951"),
-        ("O[1+3, 4+7, 5/10, R25,4^2,-50]","21.938835429438818"),
-        ("Q[3+3,-17,R144]", "48"),
-        ("5A-1", "5"),
-        ("A(5-6+A(5-50)", "44"),
-    ].iter().cloned().collect();
-
-    for (&equation, &expected) in tests.iter() {
-        let result = compute_result(equation);
-        if result != expected {
-            println!("{} expected {} but returned {}", equation, expected, result);
-            failure += 1;
-        } else {
-            success += 1;
+    for (equation, expected) in CASES {
+        lines.push(format!("Test: {}", equation));
+        match compute_result(equation) {
+            Ok(result) if results_match(&result, expected) => success += 1,
+            Ok(result) => {
+                lines.push(format!("{} expected {} but returned {}", equation, expected, result));
+                failure += 1;
+            },
+            Err(e) => {
+                lines.push(format!("{} expected {} but failed: {}", equation, expected, e));
+                failure += 1;
+            },
         }
     }
 
     let total_tests = failure + success;
     let percent_successful = success as f32 / total_tests as f32 * 100.0;
-    println!("Testing Complete!\n{} tests ran. {} tests failed. {}% successful.", 
-            total_tests, failure, percent_successful);
-}
\ No newline at end of file
+    lines.push(format!("Testing Complete!\n{} tests ran. {} tests failed. {}% successful.",
+                       total_tests, failure, percent_successful));
+    lines.join("\n")
+}
@@ -0,0 +1,23 @@
+use rusty_calculator::test::{compute_result, results_match, CASES};
+
+#[test]
+fn cases_return_expected_results() {
+    let failures: Vec<String> = CASES.iter()
+        .filter_map(|(equation, expected)| match compute_result(equation) {
+            Ok(result) if results_match(&result, expected) => None,
+            Ok(result) => Some(format!("{} expected {} but returned {}", equation, expected, result)),
+            Err(e) => Some(format!("{} expected {} but failed: {}", equation, expected, e)),
+        })
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn results_match_within_tolerance() {
+    assert!(results_match("3.9999999999999982", "4"));
+    assert!(results_match("2613070.9999998086", "2613071"));
+    assert!(results_match("[2, 2, 3]", "[2, 2, 3]"));
+    assert!(!results_match("3.99", "4"));
+    assert!(!results_match("[2, 2]", "[2, 2, 3]"));
+    assert!(!results_match("1267650600228229401496703205377", "1267650600228229401496703205376"));
+}
@@ -0,0 +1,58 @@
+use rusty_calculator::command::execute_command;
+use rusty_calculator::script::run_line;
+use rusty_calculator::session::{self, Calculation, Session};
+
+// Runs each line in a new session and returns the session afterwards.
+fn run(lines: &[&str]) -> Session {
+    let mut session = Session::new();
+    for line in lines {
+        run_line(line, &mut session);
+    }
+    session
+}
+
+#[test]
+fn results_are_referenced_by_number() {
+    let session = run(&["2*3; 4*5", "$1+$2", "$$*2", "S[1, $1, x]"]);
+    let results: Vec<&str> = session.results.iter().map(|calculation| calculation.result.as_str()).collect();
+    assert_eq!(results, ["6", "20", "26", "52", "21"]);
+    assert_eq!(session.results[2].input, "$1+$2");
+}
+
+#[test]
+fn missing_results_are_errors() {
+    let mut session = Session::new();
+    assert!(!run_line("$$", &mut session));
+    assert!(!run_line("$1", &mut session));
+    assert!(run_line("5", &mut session));
+    assert!(!run_line("$0", &mut session));
+    assert!(!run_line("$2", &mut session));
+    assert!(!run_line("$+1", &mut session));
+    assert!(run_line("2$1", &mut session));
+    assert_eq!(session.results.len(), 2);
+}
+
+#[test]
+fn history_clear_and_reset_remove_results() {
+    let mut session = run(&["1+1", "2+2"]);
+    assert!(execute_command("/history", &mut session));
+    assert!(execute_command("/history clear", &mut session));
+    assert!(session.results.is_empty());
+    assert!(!execute_command("/history all", &mut session));
+
+    run_line("3+3", &mut session);
+    assert!(run_line("$1", &mut session));
+    execute_command("/reset", &mut session);
+    assert!(session.results.is_empty());
+}
+
+#[test]
+fn results_are_saved_with_the_session() {
+    let path = std::env::temp_dir().join(format!("rusty_calculator_history_{}", std::process::id()));
+    let mut saved = run(&["S[1, 3]", "M[1, 2]"]);
+    saved.results.push(Calculation { input: "[variables]\t]".to_string(), result: "1".to_string() });
+    session::save(&saved, &path).unwrap();
+    let loaded = session::load(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(loaded.unwrap().results, saved.results);
+}
@@ -0,0 +1,141 @@
+use proptest::prelude::*;
+use rusty_calculator::command::get_variable_map;
+use rusty_calculator::infix_to_postfix::infix_to_postfix;
+use rusty_calculator::test::{compute_result, results_match};
+use rusty_calculator::tokenize::tokenize;
+use rusty_calculator::value::Value;
+
+// An equation of whole numbers and left associative operators, to be written out and solved.
+#[derive(Clone, Debug)]
+enum Expression {
+    Number(u8),
+    Operation(char, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Number(_) => u8::MAX,
+            Expression::Operation('*', _, _) => 1,
+            Expression::Operation(_, _, _) => 0,
+        }
+    }
+
+    // Writes the equation with only the parenthesis its precedence needs.
+    fn infix(&self) -> String {
+        match self {
+            Expression::Number(number) => number.to_string(),
+            Expression::Operation(operator, a, b) => {
+                let a = if a.precedence() < self.precedence() { format!("({})", a.infix()) } else { a.infix() };
+                let b = if b.precedence() <= self.precedence() { format!("({})", b.infix()) } else { b.infix() };
+                format!("{} {} {}", a, operator, b)
+            },
+        }
+    }
+
+    fn postfix(&self) -> Vec<String> {
+        match self {
+            Expression::Number(number) => vec![number.to_string()],
+            Expression::Operation(operator, a, b) => {
+                let mut postfix = a.postfix();
+                postfix.extend(b.postfix());
+                postfix.push(operator.to_string());
+                postfix
+            },
+        }
+    }
+
+    fn solve(&self) -> i128 {
+        match self {
+            Expression::Number(number) => *number as i128,
+            Expression::Operation(operator, a, b) => match operator {
+                '+' => a.solve() + b.solve(),
+                '-' => a.solve() - b.solve(),
+                _ => a.solve() * b.solve(),
+            },
+        }
+    }
+}
+
+fn expression() -> impl Strategy<Value = Expression> {
+    (0..100u8).prop_map(Expression::Number).prop_recursive(4, 16, 2, |inner| {
+        (prop_oneof![Just('+'), Just('-'), Just('*')], inner.clone(), inner)
+            .prop_map(|(operator, a, b)| Expression::Operation(operator, Box::new(a), Box::new(b)))
+    })
+}
+
+// Formats a number so it can be entered as part of an equation.
+fn operand(number: f64) -> String {
+    format!("({})", Value::Float(number))
+}
+
+proptest! {
+    #[test]
+    fn written_equations_convert_to_their_postfix(expression in expression()) {
+        let tokens = tokenize(&expression.infix(), &get_variable_map()).unwrap();
+        prop_assert_eq!(infix_to_postfix(&tokens), expression.postfix());
+    }
+
+    #[test]
+    fn written_equations_solve_to_their_value(expression in expression()) {
+        prop_assert_eq!(compute_result(&expression.infix()), Ok(expression.solve().to_string()));
+    }
+
+    #[test]
+    fn postfix_written_back_as_infix_solves_the_same(expression in expression()) {
+        // Rebuilds the equation from its postfix with every operation in parenthesis.
+        let tokens = tokenize(&expression.infix(), &get_variable_map()).unwrap();
+        let mut stack: Vec<String> = Vec::new();
+        for token in infix_to_postfix(&tokens) {
+            if matches!(token.as_str(), "+" | "-" | "*") {
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                stack.push(format!("({} {} {})", a, token, b));
+            } else {
+                stack.push(token);
+            }
+        }
+        prop_assert_eq!(stack.len(), 1);
+        prop_assert_eq!(compute_result(&stack[0]).unwrap(), compute_result(&expression.infix()).unwrap());
+    }
+
+    #[test]
+    fn printed_floats_parse_to_the_same_value(number in -1E15f64..1E15) {
+        let printed = Value::Float(number).to_string();
+        prop_assert_eq!(Value::parse(&printed).unwrap().to_f64().unwrap(), number);
+    }
+
+    #[test]
+    fn printed_integers_parse_to_the_same_value(number in any::<i128>()) {
+        let value = Value::Integer(number.into());
+        prop_assert_eq!(Value::parse(&value.to_string()), Some(value));
+    }
+
+    #[test]
+    fn printed_results_solve_to_themselves(number in -1E6f64..1E6) {
+        let result = compute_result(&operand(number)).unwrap();
+        prop_assert!(results_match(&compute_result(&result).unwrap(), &result));
+    }
+
+    #[test]
+    fn addition_is_commutative(a in -1E6f64..1E6, b in -1E6f64..1E6) {
+        let (a, b) = (operand(a), operand(b));
+        let forward = compute_result(&format!("{}+{}", a, b)).unwrap();
+        let backward = compute_result(&format!("{}+{}", b, a)).unwrap();
+        prop_assert!(results_match(&forward, &backward), "{} != {}", forward, backward);
+    }
+
+    #[test]
+    fn multiplication_is_commutative(a in any::<i64>(), b in any::<i64>()) {
+        let forward = compute_result(&format!("({})*({})", a, b));
+        let backward = compute_result(&format!("({})({})", b, a));
+        prop_assert_eq!(forward, Ok((a as i128 * b as i128).to_string()));
+        prop_assert_eq!(backward, Ok((a as i128 * b as i128).to_string()));
+    }
+
+    #[test]
+    fn double_negation_is_identity(number in -1E6f64..1E6) {
+        let result = compute_result(&format!("-(-{})", operand(number))).unwrap();
+        prop_assert!(results_match(&result, &Value::Float(number).to_string()));
+    }
+}
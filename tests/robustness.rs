@@ -0,0 +1,82 @@
+use proptest::prelude::*;
+use rusty_calculator::command::change_variable;
+use rusty_calculator::evaluate::evaluate;
+use rusty_calculator::infix_to_postfix::infix_to_postfix;
+use rusty_calculator::session;
+use rusty_calculator::tokenize::tokenize_with_context;
+
+// Solves an equation the way the pipeline fuzz target does, returning the result or error.
+fn solve(equation: &str) -> Result<String, String> {
+    let mut session = session::Session::new();
+    if equation.starts_with('_') {
+        return change_variable(equation, &mut session).map(|(_, value)| value.to_string());
+    }
+    let tokens = tokenize_with_context(equation, session.context())?;
+    evaluate(infix_to_postfix(&tokens)).map(|value| value.to_string())
+}
+
+#[test]
+fn multibyte_characters_after_underscore_are_errors() {
+    assert!(solve("_é(4)").is_err());
+    assert!(solve("_€").is_err());
+    assert!(solve("_").is_err());
+    assert_eq!(solve("_iü4"), Ok("4".to_string()));
+}
+
+#[test]
+fn stray_brackets_are_errors() {
+    assert!(solve("S[1, 2, 3]]").is_ok());
+    assert!(solve("]").is_err());
+    assert!(solve("S]").is_err());
+    assert!(solve("S[[1]").is_err());
+    assert!(solve("SS[1, 2, 3]").is_err());
+    assert!(solve("gcd[4, S[1, 2").is_err());
+}
+
+#[test]
+fn unbounded_loops_are_errors() {
+    assert!(solve("S[0, 1E300, 1]").is_err());
+    assert!(solve("P[-1E300, 0, 1]").is_err());
+    assert!(solve("S[0, 1, 1E-300, x]").is_err());
+    assert!(solve("S[1, 2, 0, x]").is_err());
+    assert!(solve("S[1, 2, -1, x]").is_err());
+    assert!(solve("S[inf, 1, x]").is_err());
+    assert!(solve("P[1, inf, 2]").is_err());
+    assert!(solve("100000!").is_err());
+    assert!(solve("ncr[10^10, 10^9]").is_err());
+}
+
+#[test]
+fn odd_postfix_expressions_are_errors() {
+    let postfix = |tokens: &[&str]| tokens.iter().map(|token| token.to_string()).collect::<Vec<String>>();
+    assert!(evaluate(postfix(&["(", "1"])).is_err());
+    assert!(evaluate(postfix(&["+"])).is_err());
+    assert!(evaluate(postfix(&["[1, 2]", "[1, 2, 3]", "+"])).is_err());
+    assert!(evaluate(postfix(&["9999999999999999999999", "!"])).is_err());
+}
+
+#[test]
+fn bracketed_history_lines_are_not_section_headers() {
+    let path = std::env::temp_dir().join(format!("rusty_calculator_robustness_{}", std::process::id()));
+    let mut saved = session::Session::new();
+    saved.history = vec!["[1, 2]".to_string(), "[settings]".to_string()];
+    session::save(&saved, &path).unwrap();
+    let loaded = session::load(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(loaded.unwrap().history, saved.history);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn no_equation_panics(equation in "[0-9a-zA-Z+\\-*/^%#\\\\!()\\[\\]{},._=;<>$@é€± ]{0,32}") {
+        let _ = solve(&equation);
+        let _ = solve(&format!("_{}", equation));
+    }
+
+    #[test]
+    fn no_token_vector_panics(tokens in prop::collection::vec("[0-9.E+\\-*/^%#\\\\!RLHA~±()\\[\\], ]{0,6}", 0..12)) {
+        let _ = evaluate(infix_to_postfix(&tokens));
+    }
+}
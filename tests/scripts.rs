@@ -0,0 +1,74 @@
+use rusty_calculator::script::{run_line, run_script};
+use rusty_calculator::session::Session;
+
+// Runs a script with a new session and returns the session afterwards.
+fn run(source: &str) -> Result<Session, String> {
+    let mut session = Session::new();
+    run_script(source, &mut session).map(|_| session)
+}
+
+#[test]
+fn loops_and_conditions_update_variables() {
+    let session = run("\
+        # Sum the odd numbers below 10\n\
+        let k = 0\n\
+        for i = 1, 10\n\
+            if i % 2\n\
+                let k = k + i\n\
+            end\n\
+        end\n\
+        let j = 5\n\
+        while j > 0\n\
+            let j = j - 1\n\
+            let n = n * 2\n\
+        end").unwrap();
+    assert_eq!(session.variables[&'k'], "25");
+    assert_eq!(session.variables[&'i'], "10");
+    assert_eq!(session.variables[&'j'], "0");
+    assert_eq!(session.variables[&'n'], "32");
+}
+
+#[test]
+fn functions_are_defined_and_called() {
+    let session = run("\
+        def sq(i) = i^2\n\
+        def hyp(i, j) = R(sq[i] + sq[j])\n\
+        let i = 7\n\
+        let k = hyp[3, 4] + sq[2]sq[1] + i\n\
+        _lsq[12]").unwrap();
+    assert_eq!(session.variables[&'k'], "16");
+    assert_eq!(session.variables[&'l'], "144");
+    assert_eq!(session.functions["hyp"].parameters, vec!['i', 'j']);
+}
+
+#[test]
+fn comma_decimal_locales_separate_values_with_semicolons() {
+    let session = run("\
+        /format locale de\n\
+        let k = 0\n\
+        for i = 0,5; 2; 0,5\n\
+            let k = k + i\n\
+        end\n\
+        let l = S[1; 1.000; 1] + M[1,5; 2,5]").unwrap();
+    assert_eq!(session.variables[&'k'], "5");
+    assert_eq!(session.variables[&'l'], "1002");
+}
+
+#[test]
+fn commands_keep_semicolon_arguments_in_comma_decimal_locales() {
+    let mut session = run("/format locale de").unwrap();
+    assert!(run_line("/plot x^2; -2; 2", &mut session));
+    assert!(run_line("/plot x(x+1); -0,5; 1,5", &mut session));
+    assert!(session.results.is_empty());
+    assert!(!run_line("/plot x^2, -2, 2", &mut session));
+}
+
+#[test]
+fn errors_report_line_numbers() {
+    assert_eq!(run("let i = 1\nfor j = 1, 3\nprint j").err(), Some("Line 2: Missing end for for".to_string()));
+    assert_eq!(run("let p = 3").err(), Some("Line 1: p is not a mutable variable".to_string()));
+    assert_eq!(run("end").err(), Some("Line 1: Unexpected end".to_string()));
+    assert!(run("let i = 1\n\nlet j = 1/0").err().is_some_and(|e| e.starts_with("Line 3:")));
+    assert!(run("def ff(i) = ff[i]\nlet i = ff[1]").is_err());
+    assert!(run("while 1\nend").is_err());
+}
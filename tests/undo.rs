@@ -0,0 +1,85 @@
+use rusty_calculator::script::{run_line, run_script};
+use rusty_calculator::session::Session;
+
+// Runs each line in order, returning whether each succeeded.
+fn run(session: &mut Session, lines: &[&str]) -> Vec<bool> {
+    lines.iter().map(|line| run_line(line, session)).collect()
+}
+
+#[test]
+fn variable_changes_are_undone_and_redone() {
+    let mut session = Session::new();
+    run(&mut session, &["_i(5)", "_j(i*2)", "i+j"]);
+    assert_eq!(session.variables[&'j'], "10");
+
+    assert_eq!(run(&mut session, &["/undo", "/undo", "/undo"]), [true, true, false]);
+    assert_eq!(session.variables[&'i'], "1");
+    assert_eq!(session.variables[&'j'], "1");
+    // Results are not changes, so the answer is kept.
+    assert_eq!(session.variables[&'='], "15");
+
+    assert_eq!(run(&mut session, &["/redo", "/redo", "/redo"]), [true, true, false]);
+    assert_eq!(session.variables[&'i'], "5");
+    assert_eq!(session.variables[&'j'], "10");
+}
+
+#[test]
+fn new_changes_clear_redo() {
+    let mut session = Session::new();
+    run(&mut session, &["_i(5)", "/undo", "_k(3)"]);
+    assert!(!run_line("/redo", &mut session));
+    assert_eq!(session.variables[&'i'], "1");
+    assert_eq!(session.variables[&'k'], "3");
+}
+
+#[test]
+fn reset_and_settings_are_undone() {
+    let mut session = Session::new();
+    run(&mut session, &["_m(7)", "/reveal", "/format sci 3", "/reset"]);
+    assert!(!session.settings.reveal);
+    assert_eq!(session.variables[&'m'], "1");
+
+    run(&mut session, &["/undo"]);
+    assert!(session.settings.reveal);
+    assert_eq!(session.variables[&'m'], "7");
+    assert_eq!(session.settings.format.notation_name(), "sci 3");
+
+    run(&mut session, &["/undo", "/undo"]);
+    assert_eq!(session.settings.format.notation_name(), "auto");
+    assert!(!session.settings.reveal);
+    assert_eq!(session.variables[&'m'], "7");
+}
+
+#[test]
+fn failed_and_unchanged_inputs_are_not_recorded() {
+    let mut session = Session::new();
+    run(&mut session, &["_i(2)", "_i(", "_i(2)", "/variables", "/format"]);
+    assert!(run_line("/undo", &mut session));
+    assert_eq!(session.variables[&'i'], "1");
+    assert!(!run_line("/undo", &mut session));
+}
+
+#[test]
+fn undone_seeds_restart_draws() {
+    let mut session = Session::new();
+    run(&mut session, &["/set seed 5", "rand[]"]);
+    let draw = session.variables[&'='].clone();
+
+    run(&mut session, &["/set seed 7", "/undo", "rand[]"]);
+    assert_eq!(session.variables[&'='], draw);
+}
+
+#[test]
+fn script_statements_are_undone() {
+    let mut session = Session::new();
+    run_script("let i = 5\ndef sq(m) = m^2\nfor j = 1, 3\nlet k = k + j\nend", &mut session).unwrap();
+    assert_eq!((session.variables[&'j'].as_str(), session.variables[&'k'].as_str()), ("3", "7"));
+
+    assert!(run_line("/undo", &mut session));
+    assert_eq!((session.variables[&'j'].as_str(), session.variables[&'k'].as_str()), ("1", "1"));
+    assert!(run_line("/undo", &mut session));
+    assert!(!session.functions.contains_key("sq"));
+    assert!(run_line("/undo", &mut session));
+    assert_eq!(session.variables[&'i'], "1");
+    assert!(!run_line("/undo", &mut session));
+}
@@ -1,26 +1,109 @@
-use crate::complex_evaluate::complex_evaluate;
+use crate::complex_evaluate::{complex_evaluate, FUNCTIONS};
+use crate::constants;
+use crate::datetime;
+use crate::format::NumberFormat;
+use crate::function::{call_function, Function};
+use crate::series::series;
+use crate::session::Calculation;
+use crate::uncertainty::Propagation;
 use std::collections::HashMap;
+use std::f64::consts;
 
-const OPERATORS: [&str; 15] = ["+", "-", "*", "/", "^", "%", "#", "(", 
-                                "\\", "R", "~", "L", "H", ",", "A"];
+const OPERATORS: [&str; 26] = ["+", "-", "*", "/", "^", "%", "#", "(", 
+                                "\\", "R", "~", "L", "H", ",", "A",
+                                "<", "<=", "==", "!=", ">", ">=", "and", "or", "not", "±", "±i"];
+
+// Boolean operators entered as words.
+pub const WORD_OPERATORS: [&str; 3] = ["and", "or", "not"];
+
+// Session values an equation may use while it is tokenized.
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
+    pub variables: &'a HashMap<char, String>,
+    pub functions: &'a HashMap<String, Function>,
+    pub format: &'a NumberFormat,
+    pub results: &'a [Calculation],
+    pub uncertainty: Propagation,
+}
 
 // Tokenize user entered equation into individual strings tokens.
 // Also evaluate complex functions and push their result as a token.
 pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<Vec<String>, String> {
+    let context = Context {
+        variables: variable_map,
+        functions: &HashMap::new(),
+        format: &NumberFormat::default(),
+        results: &[],
+        uncertainty: Propagation::Gaussian,
+    };
+    tokenize_with_context(equation, context)
+}
+
+// Tokenize an equation that may call user defined functions and print formatted values.
+pub fn tokenize_with_context(equation: &str, context: Context) -> Result<Vec<String>, String> {
+    tokenize_at_depth(equation, context, 0)
+}
+
+// Tokenize an equation within depth nested function calls.
+pub(crate) fn tokenize_at_depth(equation: &str, context: Context, depth: usize) -> Result<Vec<String>, String> {
+    let (variable_map, functions) = (context.variables, context.functions);
+    let locale = context.format.locale;
     let mut tokens: Vec<Vec<String>> = vec![Vec::new()];
     let mut index = 0;
     let mut number_buffer = String::new();
-    let mut complex_types: Vec<char> = Vec::new();
+    let mut complex_types: Vec<String> = Vec::new();
     let mut complex_tokens = false;
     let mut balanced_parenthesis: Vec<i32> = vec![0];
+    let mut skipped_characters = 0;
+
+    for (position, c) in equation.char_indices() {
+        if skipped_characters > 0 {
+            skipped_characters -= 1;
+            continue;
+        }
+        // Named complexity types and user functions take precedence over single letter variables.
+        let function = FUNCTIONS.iter().copied()
+            .chain(functions.keys().map(String::as_str))
+            .find(|name| {
+                equation[position..].strip_prefix(*name).is_some_and(|rest| rest.starts_with('['))
+            });
+        let operator = if function.is_none() {
+            let has_left_operand = !number_buffer.is_empty()
+                || tokens[index].last().is_some_and(|last| !OPERATORS.contains(&last.as_str()));
+            operator_at(equation, position, has_left_operand)
+        } else {
+            None
+        };
 
-    for c in equation.chars() {
         match c {
-            '0'..='9' | '.' | 'E' => number_buffer.push(c),
+            '0'..='9' | 'E' => number_buffer.push(c),
+            _ if c == locale.decimal_separator() => number_buffer.push('.'),
+            _ if c == locale.group_separator() && locale.groups_input() && !number_buffer.is_empty() => {},
             _ => {
                 let curr_tokens = &mut tokens[index];
+                // Push earlier results referenced by number like '$3', or '$$' for the last one,
+                // library constants like '@tau' or '@phys.c', dates and durations like '{90 days}',
+                // and infinity as 'inf'.
+                let reference = match c {
+                    '$' => Some(result_reference(&equation[position..], context.results)?),
+                    '@' => Some(constant_reference(&equation[position..])?),
+                    '{' => Some(date_literal(&equation[position..])?),
+                    'i' if standalone(equation, position, "inf") => Some(("inf".to_string(), 3)),
+                    _ => None,
+                };
+                let referenced = reference.is_some();
+                if let Some((value, length)) = reference {
+                    if !number_buffer.is_empty() {
+                        curr_tokens.push(number_buffer.clone());
+                        number_buffer.clear();
+                    }
+                    number_buffer.push_str(&value);
+                    skipped_characters = length - 1;
+                }
+
                 // Push potential variable values.
-                if let Some(value) = variable_map.get(&c) {
+                if let Some(value) = variable_map.get(&c)
+                    .filter(|_| function.is_none() && operator.is_none() && !referenced) {
                     if !number_buffer.is_empty() {
                         curr_tokens.push(number_buffer.clone());
                         number_buffer.clear();
@@ -32,25 +115,43 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                 if !number_buffer.is_empty() {
                     // If last is f64 or ')', push '*' before pushing number.
                     if curr_tokens.last()
-                    .map_or(false, |last| last == ")" || last.parse::<f64>().is_ok()) {
+                    .is_some_and(|last| last == ")" || last.parse::<f64>().is_ok()) {
                         curr_tokens.push("*".to_string());
                     }
                     curr_tokens.push(number_buffer.clone());
                     number_buffer.clear();
                 }
 
+                if let Some(operator) = operator {
+                    curr_tokens.push(operator.to_string());
+                    skipped_characters = operator.len() - 1;
+                    continue;
+                }
+
+                if let Some(name) = function {
+                    // Store named complex type, push '*' if needed, and skip the rest of its name.
+                    complex_tokens = true;
+                    complex_types.push(name.to_string());
+                    if curr_tokens.last()
+                    .is_some_and(|last| last == ")" || last.parse::<f64>().is_ok()) {
+                        curr_tokens.push("*".to_string());
+                    }
+                    skipped_characters = name.chars().count() - 1;
+                    continue;
+                }
+
                 match c {
                     '+' => {
                         // If last is None or not an operator, push '+'.
                         if curr_tokens.last()
-                        .map_or(true, |last| !OPERATORS.contains(&last.as_str())) {
+                        .is_none_or(|last| !OPERATORS.contains(&last.as_str())) {
                             curr_tokens.push(c.to_string());
                         }
                     },
                     '-' => {
                         // If last is an operator or none, push '~'. Otherwise, push '-'.
                         if curr_tokens.last()
-                        .map_or(true, |last| OPERATORS.contains(&last.as_str())) {
+                        .is_none_or(|last| OPERATORS.contains(&last.as_str())) {
                             curr_tokens.push("~".to_string());
                         } else {
                             curr_tokens.push(c.to_string());
@@ -85,7 +186,7 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                     '(' => {
                         // If last is f64 or ')', push '*' before pushing '('.
                         if curr_tokens.last()
-                        .map_or(false, |last| last == ")" || last.parse::<f64>().is_ok()) {
+                        .is_some_and(|last| last == ")" || last.parse::<f64>().is_ok()) {
                             curr_tokens.push("*".to_string());
                         }
                         curr_tokens.push(c.to_string());
@@ -96,10 +197,11 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                         balanced_parenthesis[index] -= 1;
                     }
                     '!' => curr_tokens.push(c.to_string()),
+                    _ if referenced => {},
                     'R' => {
                         // Push default root value of 2 if none is provided.
                         if curr_tokens.last()
-                        .map_or(true, |last| OPERATORS.contains(&last.as_str())) {
+                        .is_none_or(|last| OPERATORS.contains(&last.as_str())) {
                             curr_tokens.push("2".to_string());
                         }
                         curr_tokens.push(c.to_string());
@@ -107,7 +209,7 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                     'L' => {
                         // Push default log base value of 10 if none is provided.
                         if curr_tokens.last()
-                        .map_or(true, |last| OPERATORS.contains(&last.as_str())) {
+                        .is_none_or(|last| OPERATORS.contains(&last.as_str())) {
                             curr_tokens.push("10".to_string());
                         }
                         curr_tokens.push(c.to_string());
@@ -115,30 +217,56 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                     'N' => {
                         // Push e and L to simulate ln functionality.
                         if curr_tokens.last()
-                        .map_or(false, |last| !OPERATORS.contains(&last.as_str())) {
+                        .is_some_and(|last| !OPERATORS.contains(&last.as_str())) {
                             curr_tokens.push("*".to_string());
                         }
-                        curr_tokens.push("2.71828182845".to_string());
+                        curr_tokens.push(consts::E.to_string());
                         curr_tokens.push("L".to_string());
                     },
                     'H' => curr_tokens.push(c.to_string()),
+                    '±' => curr_tokens.push(context.uncertainty.operator().to_string()),
                     'A' => {
                         if curr_tokens.last()
-                        .map_or(false, |last| !OPERATORS.contains(&last.as_str())) {
+                        .is_some_and(|last| !OPERATORS.contains(&last.as_str())) {
                             curr_tokens.push("*".to_string());
                         }
                         curr_tokens.push(c.to_string());
                     },
-                    'S' | 'P' | 'M' | 'O' | 'Q' => {
+                    'S' | 'P' => {
+                        // Summations and products are computed from the text in their brackets, so their
+                        // equation can be evaluated again for each value of their bound variable.
+                        let rest = &equation[position + 1..];
+                        let spaces = rest.len() - rest.trim_start().len();
+                        let text = bracketed(&rest[spaces..])
+                            .ok_or_else(|| format!("Expected values in brackets after {}, as in {}[1, 10, x]", c, c))?;
+                        let result = series(c, text, context, depth)?;
+                        if curr_tokens.last()
+                        .is_some_and(|last| last == ")" || last.parse::<f64>().is_ok()) {
+                            curr_tokens.push("*".to_string());
+                        }
+                        curr_tokens.push(result);
+                        skipped_characters = spaces + text.chars().count() + 2;
+                    },
+                    'M' | 'O' | 'Q' => {
                         // Store complex type and push '*' if needed
                         complex_tokens = true;
-                        complex_types.push(c);
+                        complex_types.push(c.to_string());
                         if curr_tokens.last()
-                        .map_or(false, |last| last == ")" || last.parse::<f64>().is_ok()) {
+                        .is_some_and(|last| last == ")" || last.parse::<f64>().is_ok()) {
                             curr_tokens.push("*".to_string());
                         }
                     },
-                    '[' if complex_tokens => {
+                    '[' if complex_tokens || complex_types.len() == index => {
+                        // Brackets without a complexity type are a list literal, as in '[[1, 2], [3, 4]]'.
+                        if complex_types.len() == index {
+                            complex_tokens = true;
+                            complex_types.push(c.to_string());
+                            if curr_tokens.last().is_some_and(|last| {
+                                last == ")" || last.starts_with('[') || last.parse::<f64>().is_ok()
+                            }) {
+                                curr_tokens.push("*".to_string());
+                            }
+                        }
                         // Initialize new complexity level values.
                         if complex_types.len() != index + 1 {
                             return Err("Rogue brackets without complexity type".to_string());
@@ -167,11 +295,17 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                         
                         if let Some(ctokens) = tokens.pop() {
                             if let Some(ctype) = complex_types.pop() {
+                                if functions.contains_key(&ctype) {
+                                    let result = call_function(&ctype, &ctokens, context, depth)?;
+                                    tokens[index].push(result);
+                                    continue;
+                                }
                                 match complex_evaluate(&ctokens, &ctype) {
-                                    Ok(result) if ctype == 'Q' => {
-                                        let roots: Vec<&str> = result.split(',').collect();
-                                        println!("Quadratic results: {}, {}", roots[0], roots[1]);
-                                        tokens[index].push(roots[1].to_string());
+                                    Ok(result) if ctype == "Q" => {
+                                        let (first, second) = result.split_once(',').unwrap_or((&result, &result));
+                                        eprintln!("Quadratic results: {}{} {}", context.format.format_text(first),
+                                                  locale.argument_separator(), context.format.format_text(second));
+                                        tokens[index].push(second.to_string());
                                     },
                                     Ok(result) => tokens[index].push(result),
                                     Err(err) => return Err(err.to_string()),
@@ -179,28 +313,25 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
                             }
                         }
                     },
-                    ',' if complex_tokens => {
+                    _ if c == locale.argument_separator() && complex_tokens => {
                         if let Some(parenthesis) = balanced_parenthesis.pop() {
                             for _ in 0..parenthesis {
                                 curr_tokens.push(')'.to_string());
                             }
                         }
                         balanced_parenthesis.push(0);
-                        curr_tokens.push(c.to_string());
+                        curr_tokens.push(",".to_string());
                     },
                     _ => {
                         // Ignore whitespace and x when necessary.
-                        if c == 'x' {
+                        if c == 'x' && !variable_map.contains_key(&c) {
                             if let Some(last) = complex_types.last() {
-                                match last {
-                                    'S' | 'P' => curr_tokens.push(c.to_string()),
-                                    _ => println!("'x' is not a valid character for complexity 
-                                                    type {}. Solving without x.", last),
-                                }
+                                eprintln!("'x' is not a valid character for complexity 
+                                           type {}. Solving without x.", last);
                             }
                         } else if complex_types.is_empty() && c != ' ' && c != '\t' 
                             && !variable_map.contains_key(&c) {
-                            println!("'{}' is an invalid character. Solving without '{}'.", c, c);
+                            eprintln!("'{}' is an invalid character. Solving without '{}'.", c, c);
                         }
                     },
                 }
@@ -212,7 +343,7 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
     if !number_buffer.is_empty() {
         // If last is f64 or ')', push '*' before pushing number.
         if curr_tokens.last()
-        .map_or(false, |last| last == ")" || last.parse::<f64>().is_ok()) {
+        .is_some_and(|last| last == ")" || last.parse::<f64>().is_ok()) {
             curr_tokens.push("*".to_string());
         }
         curr_tokens.push(number_buffer.clone());
@@ -231,4 +362,84 @@ pub fn tokenize(equation: &str, variable_map: &HashMap<char, String>) -> Result<
         }
     }
     Err("Unable to tokenize complexities".to_string())
-}
\ No newline at end of file
+}
+
+// Returns the text inside the brackets at the start of text, up to the matching ']'.
+fn bracketed(text: &str) -> Option<&str> {
+    let inside = text.strip_prefix('[')?;
+    let mut depth = 1;
+    for (position, c) in inside.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {},
+        }
+        if depth == 0 {
+            return Some(&inside[..position]);
+        }
+    }
+    None
+}
+
+// Finds the result referenced at the start of text and the length of the reference.
+fn result_reference(text: &str, results: &[Calculation]) -> Result<(String, usize), String> {
+    if text.starts_with("$$") {
+        let last = results.last().ok_or_else(|| "No results in history for '$$'".to_string())?;
+        return Ok((last.result.clone(), 2));
+    }
+    let digits = text[1..].chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return Err("Expected a result number or '$' after '$'".to_string());
+    }
+    let reference = &text[..1 + digits];
+    text[1..1 + digits].parse::<usize>().ok()
+        .and_then(|number| results.get(number.checked_sub(1)?))
+        .map(|calculation| (calculation.result.clone(), reference.len()))
+        .ok_or_else(|| format!("Result {} is not in history", reference))
+}
+
+// Finds the library constant named at the start of text and the length of its name.
+// The name may be preceded by a namespace and '.', as in '@phys.c'.
+fn constant_reference(text: &str) -> Result<(String, usize), String> {
+    let name_length = |text: &str| text.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(text.len());
+    let mut length = 1 + name_length(&text[1..]);
+    if text[length..].strip_prefix('.').is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic())) {
+        length += 1 + name_length(&text[length + 1..]);
+    }
+    let constant = constants::find(&text[1..length])?;
+    Ok((constant.value.to_string(), length))
+}
+
+// Reads the date or duration literal at the start of text and the number of characters it spans.
+fn date_literal(text: &str) -> Result<(String, usize), String> {
+    let end = text.find('}').ok_or_else(|| "Expected '}' to close the date or duration".to_string())?;
+    let value = datetime::parse(&text[1..end])?;
+    Ok((value.to_string(), text[..=end].chars().count()))
+}
+
+// Whether word starts at position and stands alone. Words must stand alone so that variables like 'n'
+// and 'o' keep their meaning in 'non'.
+fn standalone(equation: &str, position: usize, word: &str) -> bool {
+    equation[position..].strip_prefix(word).is_some_and(|after| !after.starts_with(|c: char| c.is_ascii_alphabetic()))
+        && !equation[..position].chars().next_back().is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+// Finds a comparison or boolean operator starting at position.
+fn operator_at(equation: &str, position: usize, has_left_operand: bool) -> Option<&'static str> {
+    let rest = &equation[position..];
+    let before = equation[..position].chars().next_back();
+
+    if let Some(word) = WORD_OPERATORS.iter().find(|word| standalone(equation, position, word)) {
+        return Some(word);
+    }
+    if let Some(operator) = ["<=", ">=", "<", ">"].iter().find(|operator| rest.starts_with(**operator)) {
+        return Some(operator);
+    }
+
+    // '==' and '!=' need operands on both sides and must not be part of a longer run of '=',
+    // so that the answer variable keeps its meaning in '=*=='. '5!=120' is 5 != 120, not 5! == 120.
+    ["==", "!="].into_iter().find(|operator| {
+        rest.strip_prefix(*operator).is_some_and(|after| !after.starts_with('=') && !after.trim().is_empty())
+            && before != Some('=') && has_left_operand
+    })
+}
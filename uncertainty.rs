@@ -0,0 +1,206 @@
+use crate::evaluate::binary_operation;
+use crate::value::Value;
+use std::fmt;
+
+// How uncertainties entered with '±' are propagated through operators.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Propagation {
+    Gaussian,
+    Interval,
+}
+
+impl Propagation {
+    pub fn parse(name: &str) -> Option<Propagation> {
+        match name {
+            "gaussian" => Some(Propagation::Gaussian),
+            "interval" => Some(Propagation::Interval),
+            _ => None,
+        }
+    }
+
+    // The operator token written for '±'.
+    pub fn operator(&self) -> &'static str {
+        match self {
+            Propagation::Gaussian => "±",
+            Propagation::Interval => "±i",
+        }
+    }
+}
+
+impl fmt::Display for Propagation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Propagation::Gaussian => write!(f, "gaussian"),
+            Propagation::Interval => write!(f, "interval"),
+        }
+    }
+}
+
+// A measured value. Gaussian values have a standard error, written as '9.81±0.02', and intervals have
+// bounds, written as '9.79..9.83'.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Uncertain {
+    Gaussian { value: f64, error: f64 },
+    Interval { low: f64, high: f64 },
+}
+
+impl Uncertain {
+    // Parses an uncertain value token.
+    pub fn parse(token: &str) -> Option<Uncertain> {
+        if let Some((value, error)) = token.split_once('±') {
+            let (value, error) = (value.parse().ok()?, error.parse::<f64>().ok()?);
+            return Some(Uncertain::Gaussian { value, error: error.abs() });
+        }
+        let (low, high) = token.split_once("..")?;
+        let (low, high) = (low.parse().ok()?, high.parse().ok()?);
+        (low <= high).then_some(Uncertain::Interval { low, high })
+    }
+
+    // Returns the lowest and highest values within the uncertainty.
+    pub fn bounds(&self) -> (f64, f64) {
+        match *self {
+            Uncertain::Gaussian { value, error } => (value - error, value + error),
+            Uncertain::Interval { low, high } => (low, high),
+        }
+    }
+
+    pub fn negate(&self) -> Uncertain {
+        match *self {
+            Uncertain::Gaussian { value, error } => Uncertain::Gaussian { value: -value, error },
+            Uncertain::Interval { low, high } => Uncertain::Interval { low: -high, high: -low },
+        }
+    }
+
+    pub fn abs(&self) -> Uncertain {
+        match *self {
+            Uncertain::Gaussian { value, error } => Uncertain::Gaussian { value: value.abs(), error },
+            Uncertain::Interval { low, high } if low < 0.0 && high > 0.0 => {
+                Uncertain::Interval { low: 0.0, high: high.max(-low) }
+            },
+            Uncertain::Interval { low, high } => {
+                Uncertain::Interval { low: low.abs().min(high.abs()), high: low.abs().max(high.abs()) }
+            },
+        }
+    }
+}
+
+impl fmt::Display for Uncertain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Uncertain::Gaussian { value, error } => write!(f, "{}±{}", value, error),
+            Uncertain::Interval { low, high } => write!(f, "{}..{}", low, high),
+        }
+    }
+}
+
+// Creates an uncertain value from 'value ± error'. The operator token chooses how it is propagated.
+pub fn create(operator: &str, value: &Value, error: &Value) -> Result<Value, String> {
+    if matches!(value, Value::Uncertain(_)) || matches!(error, Value::Uncertain(_)) {
+        return Err("± needs numbers without uncertainties on both sides".to_string());
+    }
+    let (value, error) = (value.to_f64()?, error.to_f64()?.abs());
+    let uncertain = match operator {
+        "±i" => Uncertain::Interval { low: value - error, high: value + error },
+        _ => Uncertain::Gaussian { value, error },
+    };
+    Ok(Value::Uncertain(uncertain))
+}
+
+// Applies a binary operator where at least one operand is uncertain. Numbers have no uncertainty.
+// Gaussian errors are propagated to first order: each operand's contribution is half the change in the
+// result when it moves across its error, and contributions are added in quadrature.
+// If either operand is an interval, the result spans the operator's values at the operand bounds.
+pub fn operation(operator: &str, a: Value, b: Value) -> Result<Value, String> {
+    let (a, b) = (uncertain(&a)?, uncertain(&b)?);
+    let f = |x: f64, y: f64| binary_operation(operator, Value::Float(x), Value::Float(y))?.to_f64();
+
+    let result = match (a, b) {
+        (Uncertain::Gaussian { value: x, error: x_error }, Uncertain::Gaussian { value: y, error: y_error }) => {
+            let x_change = if x_error == 0.0 { 0.0 } else { (f(x + x_error, y)? - f(x - x_error, y)?) / 2.0 };
+            let y_change = if y_error == 0.0 { 0.0 } else { (f(x, y + y_error)? - f(x, y - y_error)?) / 2.0 };
+            Uncertain::Gaussian { value: f(x, y)?, error: x_change.hypot(y_change) }
+        },
+        _ => {
+            let ((x_low, x_high), (y_low, y_high)) = (a.bounds(), b.bounds());
+            if operator == "/" && y_low <= 0.0 && y_high >= 0.0 {
+                return Err("Cannot divide by an interval containing 0".to_string());
+            }
+            let mut xs = vec![x_low, x_high];
+            // Even powers are lowest at 0, which may be inside the interval.
+            if operator == "^" && x_low < 0.0 && x_high > 0.0 {
+                xs.push(0.0);
+            }
+            let mut results = Vec::new();
+            for x in xs {
+                for y in [y_low, y_high] {
+                    results.push(f(x, y)?);
+                }
+            }
+            let low = results.iter().copied().fold(f64::INFINITY, f64::min);
+            let high = results.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            Uncertain::Interval { low, high }
+        },
+    };
+    Ok(Value::Uncertain(result))
+}
+
+// Converts an operand to an uncertain value. Numbers have no uncertainty.
+fn uncertain(value: &Value) -> Result<Uncertain, String> {
+    match value {
+        Value::Uncertain(uncertain) => Ok(*uncertain),
+        value => Ok(Uncertain::Gaussian { value: value.to_f64()?, error: 0.0 }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gaussian(value: f64, error: f64) -> Value {
+        Value::Uncertain(Uncertain::Gaussian { value, error })
+    }
+
+    fn interval(low: f64, high: f64) -> Value {
+        Value::Uncertain(Uncertain::Interval { low, high })
+    }
+
+    fn close(result: Result<Value, String>, expected: Value) -> bool {
+        let (Ok(Value::Uncertain(result)), Value::Uncertain(expected)) = (result, expected) else { return false };
+        let ((a, b), (c, d)) = (result.bounds(), expected.bounds());
+        (a - c).abs() < 1E-12 && (b - d).abs() < 1E-12
+    }
+
+    #[test]
+    fn parses_and_writes_uncertain_values() {
+        assert_eq!(Uncertain::parse("9.81±0.02"), Some(Uncertain::Gaussian { value: 9.81, error: 0.02 }));
+        assert_eq!(Uncertain::parse("-1..2.5"), Some(Uncertain::Interval { low: -1.0, high: 2.5 }));
+        assert_eq!(Uncertain::parse("2..1"), None);
+        assert_eq!(Uncertain::parse("1.5"), None);
+        assert_eq!(gaussian(9.81, 0.02).to_string(), "9.81±0.02");
+        assert_eq!(Value::parse("[1±0.5, 0..1]"), Some(Value::List(vec![gaussian(1.0, 0.5), interval(0.0, 1.0)])));
+    }
+
+    #[test]
+    fn propagates_gaussian_errors() {
+        let x = gaussian(10.0, 0.3);
+        let y = gaussian(5.0, 0.4);
+        assert!(close(operation("+", x.clone(), y.clone()), gaussian(15.0, 0.5)));
+        assert!(close(operation("*", x.clone(), Value::Float(2.0)), gaussian(20.0, 0.6)));
+        // Relative errors of 3% and 8% give a relative error of about 8.5%.
+        let Ok(Value::Uncertain(Uncertain::Gaussian { value, error })) = operation("*", x, y) else { panic!() };
+        assert_eq!(value, 50.0);
+        assert!((error / value - 0.03f64.hypot(0.08)).abs() < 1E-12);
+        assert!(create("±", &gaussian(1.0, 1.0), &Value::Float(1.0)).is_err());
+    }
+
+    #[test]
+    fn propagates_interval_bounds() {
+        let x = interval(-1.0, 2.0);
+        assert!(close(operation("*", x.clone(), interval(3.0, 4.0)), interval(-4.0, 8.0)));
+        assert!(close(operation("^", x.clone(), Value::Float(2.0)), interval(0.0, 4.0)));
+        assert!(close(operation("-", Value::Float(1.0), gaussian(1.0, 0.5)), gaussian(0.0, 0.5)));
+        assert!(close(operation("+", x.clone(), gaussian(1.0, 0.5)), interval(-0.5, 3.5)));
+        assert!(operation("/", Value::Float(1.0), x.clone()).is_err());
+        assert_eq!(x.to_string(), "-1..2");
+    }
+}
@@ -0,0 +1,151 @@
+use crate::format::NumberFormat;
+use crate::function::Function;
+use crate::probability;
+use crate::session::Session;
+use crate::settings::{self, Settings};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+// Maximum number of changes that can be undone.
+const UNDO_LIMIT: usize = 100;
+
+// Variables, settings, and functions restored by /undo and /redo.
+#[derive(Clone, PartialEq)]
+struct State {
+    variables: HashMap<char, String>,
+    settings: Settings,
+    functions: HashMap<String, Function>,
+}
+
+impl State {
+    // Copies the restorable values of a session.
+    fn of(session: &Session) -> State {
+        State {
+            variables: session.variables.clone(),
+            settings: session.settings,
+            functions: session.functions.clone(),
+        }
+    }
+}
+
+// An input that changed the session, with the state before and after it.
+struct Change {
+    input: String,
+    before: State,
+    after: State,
+}
+
+// Changes that can be undone and redone, most recent last.
+#[derive(Default)]
+pub struct Changes {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+// Returns true for commands that move through changes instead of making them.
+pub fn is_undo_command(command: &str) -> bool {
+    matches!(command.split(' ').next(), Some("/undo" | "/redo"))
+}
+
+// Runs an input that may change variables or settings and records the change so it can be undone.
+// Changes made by inputs nested within it, like script lines, become part of its change.
+pub fn record<T>(input: &str, session: &mut Session, change: impl FnOnce(&mut Session) -> T) -> T {
+    let before = State::of(session);
+    let depth = session.changes.undo.len();
+    let result = change(session);
+
+    let after = State::of(session);
+    let changes = &mut session.changes;
+    changes.undo.truncate(depth);
+    if before != after {
+        changes.undo.push(Change { input: input.to_string(), before, after });
+        if changes.undo.len() > UNDO_LIMIT {
+            changes.undo.remove(0);
+        }
+        changes.redo.clear();
+    }
+    result
+}
+
+// Reverts the most recent change and describes what was reverted.
+pub fn undo(session: &mut Session) -> Result<String, String> {
+    let change = session.changes.undo.pop().ok_or_else(|| "Nothing to undo".to_string())?;
+    restore(session, &change.after, &change.before);
+    let text = report("Undone", &change.input, &describe(&change.after, &change.before, &session.settings.format));
+    session.changes.redo.push(change);
+    Ok(text)
+}
+
+// Applies the most recently undone change again and describes what was applied.
+pub fn redo(session: &mut Session) -> Result<String, String> {
+    let change = session.changes.redo.pop().ok_or_else(|| "Nothing to redo".to_string())?;
+    restore(session, &change.before, &change.after);
+    let text = report("Redone", &change.input, &describe(&change.before, &change.after, &session.settings.format));
+    session.changes.undo.push(change);
+    Ok(text)
+}
+
+// Sets only the values that differ between from and to, so later unrelated changes are kept.
+// Settings depend on each other, so they are restored together.
+fn restore(session: &mut Session, from: &State, to: &State) {
+    restore_map(&mut session.variables, &from.variables, &to.variables);
+    restore_map(&mut session.functions, &from.functions, &to.functions);
+    if from.settings != to.settings {
+        session.settings = to.settings;
+    }
+    // A restored seed restarts the random number generator, as setting it does.
+    if from.settings.seed != to.settings.seed {
+        probability::seed(session.settings.seed);
+    }
+}
+
+// Sets the entries of current that differ between from and to to their values in to.
+fn restore_map<K: Clone + Eq + Hash, V: Clone + PartialEq>(current: &mut HashMap<K, V>, from: &HashMap<K, V>,
+                                                           to: &HashMap<K, V>) {
+    for key in from.keys().chain(to.keys()) {
+        match to.get(key) {
+            Some(value) if from.get(key) != Some(value) => {
+                current.insert(key.clone(), value.clone());
+            },
+            None => {
+                current.remove(key);
+            },
+            _ => {},
+        }
+    }
+}
+
+// Describes each value that differs between from and to as 'name: old -> new'.
+fn describe(from: &State, to: &State, format: &NumberFormat) -> Vec<String> {
+    let mut lines = describe_map(&from.variables, &to.variables, |_, value| format.format_text(value));
+    for name in settings::NAMES {
+        let (before, after) = (from.settings.get(name), to.settings.get(name));
+        if before != after {
+            lines.push(format!("{}: {} -> {}", name, before.unwrap_or_default(), after.unwrap_or_default()));
+        }
+    }
+    lines.extend(describe_map(&from.functions, &to.functions, |name, function| function.definition(name)));
+    lines
+}
+
+// Describes the entries that differ between two maps, sorted by key. Missing entries are 'none'.
+fn describe_map<K: Display + Eq + Hash + Ord, V: PartialEq>(from: &HashMap<K, V>, to: &HashMap<K, V>,
+                                                           show: impl Fn(&K, &V) -> String) -> Vec<String> {
+    let mut keys: Vec<&K> = from.keys().chain(to.keys().filter(|key| !from.contains_key(key))).collect();
+    keys.sort();
+    let show = |key: &K, value: Option<&V>| value.map_or("none".to_string(), |value| show(key, value));
+    keys.into_iter()
+        .filter(|key| from.get(key) != to.get(key))
+        .map(|key| format!("{}: {} -> {}", key, show(key, from.get(key)), show(key, to.get(key))))
+        .collect()
+}
+
+// Writes description lines below an undone or redone input.
+fn report(action: &str, input: &str, lines: &[String]) -> String {
+    let mut text = format!("{}: {}", action, input);
+    for line in lines {
+        text.push_str(&format!("\n| {}", line));
+    }
+    text
+}
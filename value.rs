@@ -0,0 +1,148 @@
+use crate::datetime;
+use crate::uncertainty::Uncertain;
+use jiff::{SignedDuration, Zoned};
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use std::fmt;
+
+// A single operand. Integers are kept exact until an operation requires a float.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Integer(BigInt),
+    Float(f64),
+    List(Vec<Value>),
+    Date(Zoned),
+    Duration(SignedDuration),
+    Uncertain(Uncertain),
+}
+
+impl Value {
+    // Parses an operand token into a value.
+    pub fn parse(token: &str) -> Option<Value> {
+        let token = token.trim();
+        if let Some(inner) = token.strip_prefix('{').and_then(|rest| rest.strip_suffix('}')) {
+            return datetime::parse(inner).ok();
+        }
+        if let Some(inner) = token.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            if inner.trim().is_empty() {
+                return Some(Value::List(Vec::new()));
+            }
+            return split_list(inner).iter()
+                .map(|item| Value::parse(item))
+                .collect::<Option<Vec<Value>>>()
+                .map(Value::List);
+        }
+
+        let digits = token.strip_prefix('-').unwrap_or(token);
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            return token.parse::<BigInt>().ok().map(Value::Integer);
+        }
+        if let Some(uncertain) = Uncertain::parse(token) {
+            return Some(Value::Uncertain(uncertain));
+        }
+        token.parse::<f64>().ok().map(Value::Float)
+    }
+
+    // Converts a value to a float, failing for lists, dates, and durations.
+    pub fn to_f64(&self) -> Result<f64, String> {
+        match self {
+            Value::Integer(integer) => Ok(integer.to_f64().unwrap_or(f64::NAN)),
+            Value::Float(float) => Ok(*float),
+            Value::List(_) => Err(format!("Expected a number but found the list {}", self)),
+            Value::Date(_) => Err(format!("Expected a number but found the date {}", self)),
+            Value::Duration(_) => Err(format!("Expected a number but found the duration {}", self)),
+            Value::Uncertain(_) => Err(format!("Expected a number but found the uncertain value {}", self)),
+        }
+    }
+
+    // Converts a truth value to 1 or 0.
+    pub fn from_bool(value: bool) -> Value {
+        Value::Integer(BigInt::from(value as u8))
+    }
+
+    // Values are true when they are not 0. Lists have no truth value.
+    pub fn is_true(&self) -> Result<bool, String> {
+        match self {
+            Value::Integer(integer) => Ok(!integer.is_zero()),
+            value => Ok(value.to_f64()? != 0.0),
+        }
+    }
+
+    // Converts a value to an exact integer, failing for fractional floats and values that are not numbers.
+    pub fn to_integer(&self) -> Result<BigInt, String> {
+        match self {
+            Value::Integer(integer) => Ok(integer.clone()),
+            Value::Float(float) if float.fract() == 0.0 => BigInt::from_f64(*float)
+                .ok_or_else(|| format!("{} is not an integer", float)),
+            Value::Float(float) => Err(format!("{} is not an integer", float)),
+            Value::List(_) => Err(format!("Expected an integer but found the list {}", self)),
+            Value::Date(_) => Err(format!("Expected an integer but found the date {}", self)),
+            Value::Duration(_) => Err(format!("Expected an integer but found the duration {}", self)),
+            Value::Uncertain(_) => Err(format!("Expected an integer but found the uncertain value {}", self)),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Integer(integer) => write!(f, "{}", integer),
+            Value::Float(float) => write!(f, "{}", float),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            },
+            Value::Date(date) => write!(f, "{{{}}}", date),
+            Value::Duration(duration) => write!(f, "{{{}}}", datetime::duration_text(*duration)),
+            Value::Uncertain(uncertain) => write!(f, "{}", uncertain),
+        }
+    }
+}
+
+// Splits list contents on commas that are not nested within inner brackets.
+fn split_list(contents: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, c) in contents.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&contents[start..position]);
+                start = position + 1;
+            },
+            _ => {},
+        }
+    }
+    items.push(&contents[start..]);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_integers_exactly() {
+        let value = Value::parse("123456789012345678901234567890").unwrap();
+        assert_eq!(value.to_string(), "123456789012345678901234567890");
+        assert_eq!(Value::parse("-7"), Some(Value::Integer(BigInt::from(-7))));
+    }
+
+    #[test]
+    fn parses_floats_and_lists() {
+        assert_eq!(Value::parse("4E10"), Some(Value::Float(4E10)));
+        assert_eq!(Value::parse("[]"), Some(Value::List(Vec::new())));
+        let list = Value::parse("[1, 2.5, [3]]").unwrap();
+        assert_eq!(list.to_string(), "[1, 2.5, [3]]");
+        assert_eq!(Value::parse("[1, x]"), None);
+    }
+
+    #[test]
+    fn converts_integral_floats_to_integers() {
+        assert_eq!(Value::Float(12.0).to_integer(), Ok(BigInt::from(12)));
+        assert!(Value::Float(1.5).to_integer().is_err());
+        assert!(Value::Float(f64::INFINITY).to_integer().is_err());
+    }
+}